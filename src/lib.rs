@@ -3,25 +3,53 @@ use std::time::SystemTime;
 pub mod utils;
 
 macro_rules! define_float_choice {
-    ($float:ty, $zoom_delta:expr) => {
+    ($float:ty, $shader_float:ty, $zoom_delta:expr, $shader_zoom_delta:expr) => {
         /// Float type used for computations.
         pub type FloatChoice = $float;
+        /// Float type used by the compute shader, matching its `float` alias.
+        pub type ShaderFloat = $shader_float;
         /// Maximum zoom delta to prevent floating point errors.
         /// The zoom delta is the difference between the maximum and minimum
         /// values of the x and y ranges.
         pub const MAX_ZOOM_DELTA: FloatChoice = $zoom_delta;
+        /// Maximum zoom delta of the kernels which compute with
+        /// [`ShaderFloat`] alone, without double-single arithmetic.
+        pub const MAX_SHADER_ZOOM_DELTA: FloatChoice = $shader_zoom_delta;
     };
 }
 
-#[cfg(not(feature = "f64"))]
-define_float_choice!(f32, 1e-5);
+#[cfg(all(feature = "f64", feature = "double-single"))]
+compile_error!("The `f64` and `double-single` features are mutually exclusive");
+
+#[cfg(not(any(feature = "f64", feature = "double-single")))]
+define_float_choice!(f32, f32, 1e-5, 1e-5);
 
 #[cfg(feature = "f64")]
-define_float_choice!(f64, 1e-13);
+define_float_choice!(f64, f64, 1e-13, 1e-13);
+
+// The ranges are passed to the shader as pairs of f32, which hold about twice
+// as many significant bits as a single f32, but only the kernels computing in
+// double-single benefit from them
+#[cfg(all(feature = "double-single", not(feature = "f64")))]
+define_float_choice!(f64, f32, 1e-10, 1e-5);
+
+/// Converts a float used for computations to `f64`, which is exact.
+#[cfg(not(any(feature = "f64", feature = "double-single")))]
+pub fn to_f64(value: FloatChoice) -> f64 {
+    value as f64
+}
+
+/// Converts a float used for computations to `f64`, which is exact.
+#[cfg(any(feature = "f64", feature = "double-single"))]
+pub fn to_f64(value: FloatChoice) -> f64 {
+    value
+}
 
 /// Initial render range in x for function.
 const INITIAL_X_RANGE: [FloatChoice; 2] = [-2.0, 0.50];
 const INITIAL_Y_RANGE: [FloatChoice; 2] = [-1.25, 1.25];
+/// Initial constant c of the Julia set.
+const INITIAL_JULIA_C: [FloatChoice; 2] = [-0.8, 0.156];
 
 /// Returns the path to the save file with a unique name based on the current
 /// time.
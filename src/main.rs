@@ -1,8 +1,25 @@
 use std::cell::RefCell;
 
 use faraday_art::{
-    FloatChoice, MAX_ZOOM_DELTA, get_save_path,
-    utils::{math::*, pipeline::GPUPipeline, pipeline_buffers::ComputeData},
+    FloatChoice, get_save_path, to_f64,
+    utils::{
+        coloring::{Coloring, InteriorColoring},
+        flow::{FlowView, LicColoring},
+        formula::{Formula, FormulaKind, FormulaSet},
+        integrator::Integrator,
+        interior::InteriorCheck,
+        kernels::Kernel,
+        math::*,
+        orbit_trap::{TrapShape, TrapValue},
+        perturbation::{DeepZoomView, MAX_DEEP_ZOOM_DELTA},
+        pipeline::GPUPipeline,
+        pipeline_buffers::{
+            ComputeData, LimitCycleData, LyapunovData, MAX_NEWTON_ROOTS, MAX_REFERENCE_ORBIT_LEN,
+            NewtonData, OrbitTrapData,
+        },
+        reference::{ReferenceCache, ReferenceParams},
+        shader_watcher::{SHADER_DIRECTORY, ShaderWatcher},
+    },
 };
 use nannou::prelude::*;
 use nannou_egui::{
@@ -29,6 +46,10 @@ struct State {
     shift_speed: u32,
     /// Whether to save the image or not.
     save_image: bool,
+    /// Whether deep zooms skip iterations using a BLA table.
+    use_bla: bool,
+    /// Whether the shaders are reloaded from the source tree when they change.
+    hot_reload: bool,
 }
 
 impl Default for State {
@@ -41,6 +62,8 @@ impl Default for State {
             shift_speed: 50,
             mouse_pos: (0.0, 0.0),
             save_image: false,
+            use_bla: true,
+            hot_reload: false,
         }
     }
 }
@@ -51,8 +74,57 @@ struct Model {
     pipeline: RefCell<GPUPipeline>,
     /// Struct containing the data to be processed by the compute shader.
     compute_data: ComputeData,
+    /// Compute data remembered for each kernel, indexed by [`Kernel::index`].
+    /// This allows switching kernels without losing their view and parameters.
+    kernel_compute_data: [ComputeData; Kernel::ALL.len()],
+    /// View of the perturbation kernel, whose center is stored with arbitrary
+    /// precision to allow for deep zooms.
+    deep_view: DeepZoomView,
+    /// Reference orbit of the perturbation kernel, rebuilt in the background
+    /// when the view or the parameters it depends on change.
+    references: ReferenceCache,
     /// Indicates whether the compute data buffer needs to be updated.
     update_compute_data_buffer: RefCell<bool>,
+    /// Polynomial used by the Newton kernel.
+    newton_data: NewtonData,
+    /// Indicates whether the Newton data buffer needs to be updated.
+    update_newton_data_buffer: RefCell<bool>,
+    /// A/B sequence used by the Lyapunov kernel.
+    lyapunov_data: LyapunovData,
+    /// Indicates whether the Lyapunov data buffer needs to be updated.
+    update_lyapunov_data_buffer: RefCell<bool>,
+    /// A/B sequence as typed in the UI.
+    lyapunov_sequence: String,
+    /// Error of the last A/B sequence typed in the UI, if any.
+    lyapunov_error: Option<String>,
+    /// Limit cycle of the Van der Pol oscillator, computed for the current
+    /// mu when the limit cycle view is shown.
+    limit_cycle_data: LimitCycleData,
+    /// Orbit trap used by the orbit trap coloring.
+    orbit_trap_data: OrbitTrapData,
+    /// Indicates whether the orbit trap data buffer needs to be updated.
+    update_orbit_trap_data_buffer: RefCell<bool>,
+    /// Path of the image of the image trap, as typed in the UI.
+    trap_image_path: String,
+    /// Path of an image waiting to be loaded into the image trap.
+    pending_trap_image: Option<String>,
+    /// Error of the last image loaded into the image trap, if any.
+    trap_image_error: Option<String>,
+    /// User formulas compiled into the compute shader.
+    formulas: FormulaSet,
+    /// Formulas as typed in the UI, indexed by [`FormulaKind`].
+    formula_sources: [String; FormulaKind::ALL.len()],
+    /// Valid formulas waiting to be compiled into the compute shader.
+    pending_formulas: Option<FormulaSet>,
+    /// Error of the last formula typed in the UI, if any.
+    formula_error: Option<String>,
+    /// Watcher of the shader files, while they are hot reloaded.
+    shader_watcher: Option<ShaderWatcher>,
+    /// Errors of the last reloaded shaders, if any.
+    shader_error: Option<String>,
+    /// Number of pixels stopped early by each interior check in the last
+    /// computed texture, indexed by [`InteriorCheck::index`].
+    early_exits: [u32; InteriorCheck::ALL.len()],
     /// Indicates whether the texture needs to be recomputed.
     recompute_texture: RefCell<bool>,
 }
@@ -101,14 +173,54 @@ fn model(app: &App) -> Model {
     let egui = Egui::from_window(&window);
 
     let compute_data = ComputeData::default();
-    let pipeline = GPUPipeline::new(&window, compute_data);
+    let newton_data = NewtonData::default();
+    let orbit_trap_data = OrbitTrapData::default();
+    let lyapunov_data = LyapunovData::default();
+    let limit_cycle_data = LimitCycleData::for_mu(to_f64(compute_data.mu as FloatChoice) as f32);
+    let (x_range, y_range) = Kernel::DeepMandelbrot.initial_ranges();
+    let deep_view = DeepZoomView::from_ranges(
+        (to_f64(x_range[0]), to_f64(x_range[1])),
+        (to_f64(y_range[0]), to_f64(y_range[1])),
+    );
+    let formulas = FormulaSet::default();
+    let pipeline = GPUPipeline::new(
+        &window,
+        compute_data,
+        newton_data,
+        orbit_trap_data,
+        lyapunov_data,
+        limit_cycle_data,
+        &formulas,
+    );
 
     Model {
         egui,
         state,
         pipeline: pipeline.into(),
         compute_data,
+        kernel_compute_data: Kernel::ALL.map(ComputeData::for_kernel),
+        deep_view,
+        references: ReferenceCache::new(),
         update_compute_data_buffer: false.into(),
+        newton_data,
+        update_newton_data_buffer: false.into(),
+        lyapunov_data,
+        update_lyapunov_data_buffer: false.into(),
+        lyapunov_sequence: lyapunov_data.get_sequence(),
+        lyapunov_error: None,
+        limit_cycle_data,
+        orbit_trap_data,
+        update_orbit_trap_data_buffer: false.into(),
+        trap_image_path: String::new(),
+        pending_trap_image: None,
+        trap_image_error: None,
+        formulas,
+        formula_sources: FormulaKind::ALL.map(|kind| kind.default_source().to_string()),
+        pending_formulas: None,
+        formula_error: None,
+        shader_watcher: None,
+        shader_error: None,
+        early_exits: [0; InteriorCheck::ALL.len()],
         recompute_texture: true.into(),
     }
 }
@@ -127,10 +239,143 @@ fn view(_app: &App, model: &Model, frame: Frame) {
 }
 
 fn update(app: &App, model: &mut Model, update: Update) {
+    // Compile the new formulas, keeping the previous ones if they fail
+    if let Some(formulas) = model.pending_formulas.take() {
+        let window = app.main_window();
+        let result = model
+            .pipeline
+            .borrow_mut()
+            .update_formulas(window.device(), &formulas);
+        match result {
+            Ok(()) => {
+                model.formulas = formulas;
+                model.formula_error = None;
+                model.recompute_texture.replace(true);
+            }
+            Err(error) => model.formula_error = Some(error),
+        }
+    }
+
+    // Load the new image of the image trap, keeping the previous one if it
+    // cannot be read
+    if let Some(path) = model.pending_trap_image.take() {
+        match nannou::image::open(&path) {
+            Ok(image) => {
+                let window = app.main_window();
+                let (device, queue) = {
+                    let pair = window.device_queue_pair();
+                    (pair.device(), pair.queue())
+                };
+                let result =
+                    model
+                        .pipeline
+                        .borrow_mut()
+                        .update_trap_image(device, queue, &image.to_rgba8());
+                model.trap_image_error = result.err().map(|error| format!("{path}: {error}"));
+                model.recompute_texture.replace(true);
+            }
+            Err(error) => model.trap_image_error = Some(format!("{path}: {error}")),
+        }
+    }
+
+    // Reload the shaders modified on disk, keeping the last valid pipelines
+    if model.state.hot_reload {
+        let watcher = model.shader_watcher.get_or_insert_with(|| {
+            let names = GPUPipeline::SHADER_FILES.map(|(name, _)| name);
+            ShaderWatcher::new(SHADER_DIRECTORY, &names)
+        });
+        match watcher.changed_files() {
+            Ok(changed) if changed.is_empty() => {}
+            Ok(changed) => {
+                let window = app.main_window();
+                let result = model
+                    .pipeline
+                    .borrow_mut()
+                    .update_shader_sources(window.device(), changed);
+                model.shader_error = result.err();
+                model.recompute_texture.replace(true);
+            }
+            Err(error) => model.shader_error = Some(error),
+        }
+    } else if model.shader_watcher.is_some() {
+        // Go back to the shaders compiled into the application
+        model.shader_watcher = None;
+        let window = app.main_window();
+        let result = model
+            .pipeline
+            .borrow_mut()
+            .update_shader_sources(window.device(), GPUPipeline::embedded_shader_sources());
+        model.shader_error = result.err();
+        model.recompute_texture.replace(true);
+    }
+
+    // Collect the early exit counters of a previous compute once the GPU is
+    // done with them, without waiting for it
+    {
+        let window = app.main_window();
+        let early_exits = model.pipeline.borrow_mut().poll_early_exits(
+            window.device(),
+            window.queue(),
+            model.compute_data.kernel,
+        );
+        if let Some(early_exits) = early_exits {
+            model.early_exits = early_exits;
+        }
+    }
+
+    // Upload the reference orbit once it is built, and render the view it
+    // was built for. It is kept until the kernel is selected again
+    // otherwise, so that the data of another kernel is not modified.
+    let reference = match model.compute_data.kernel {
+        Kernel::DeepMandelbrot => model.references.poll(),
+        _ => None,
+    };
+    if let Some(reference) = reference {
+        let window = app.main_window();
+        let pipeline = model.pipeline.borrow();
+        pipeline.update_reference_orbit_buffer(window.queue(), &reference.orbit);
+        let bla_levels = match &reference.bla_table {
+            Some(bla_table) => {
+                pipeline.update_bla_table_buffer(window.queue(), bla_table.steps());
+                bla_table.levels()
+            }
+            None => 0,
+        };
+
+        let (delta_span, delta_exponent) = reference.params.view.get_scaled_size();
+        model.compute_data.update_perturbation(
+            delta_span,
+            delta_exponent,
+            reference.orbit.len() as u32,
+            bla_levels,
+        );
+        model.update_compute_data_buffer.replace(true);
+        model.recompute_texture.replace(true);
+    }
+
+    // The perturbation kernel needs a reference orbit at the center of the
+    // view, computed with arbitrary precision. It is only rebuilt when it
+    // changes, in the background, and the current one is used until then.
+    if model.compute_data.kernel == Kernel::DeepMandelbrot
+        && *model.update_compute_data_buffer.borrow()
+    {
+        model.references.request(ReferenceParams {
+            view: model.deep_view.clone(),
+            max_iter: model.compute_data.max_iter,
+            bailout: model.compute_data.bailout,
+            use_bla: model.state.use_bla,
+        });
+    }
+
     let state = &mut model.state;
 
+    // The perturbation kernel has nothing to iterate until its first
+    // reference orbit is built
+    let waiting_for_reference =
+        model.compute_data.kernel == Kernel::DeepMandelbrot && !model.references.is_built();
+
     // Check if a texture recompute is requested
-    if *model.recompute_texture.borrow() || state.continuous_compute {
+    if (*model.recompute_texture.borrow() || state.continuous_compute) && !waiting_for_reference {
         // Get the device and queue from the window
         let window = app.main_window();
         let (device, queue) = {
@@ -145,19 +390,73 @@ fn update(app: &App, model: &mut Model, update: Update) {
             label: Some("Compute Encoder"),
         });
 
+        // The Buddhabrot, the attractors and the Poincaré sections accumulate
+        // new samples at each compute, and restart from their first samples
+        // when the view or their parameters change
+        let accumulate = model.compute_data.accumulates_density();
+        let restart = *model.update_compute_data_buffer.borrow();
+        if accumulate {
+            if restart {
+                model.compute_data.reset_accumulation();
+            } else {
+                model.compute_data.next_accumulation();
+                model.update_compute_data_buffer.replace(true);
+            }
+        }
+
         // Check if the data buffer needs to be updated
         if *model.update_compute_data_buffer.borrow() {
             pipeline.update_compute_data_buffer(device, &mut encoder, model.compute_data);
             model.update_compute_data_buffer.replace(false);
         }
 
+        // Check if the Newton data buffer needs to be updated
+        if *model.update_newton_data_buffer.borrow() {
+            pipeline.update_newton_data_buffer(queue, &model.newton_data);
+            model.update_newton_data_buffer.replace(false);
+        }
+
+        // Check if the Lyapunov data buffer needs to be updated
+        if *model.update_lyapunov_data_buffer.borrow() {
+            pipeline.update_lyapunov_data_buffer(queue, &model.lyapunov_data);
+            model.update_lyapunov_data_buffer.replace(false);
+        }
+
+        // Recompute the limit cycle of the Van der Pol oscillator once mu
+        // changed
+        let mu = to_f64(model.compute_data.mu as FloatChoice) as f32;
+        if model.compute_data.kernel == Kernel::VanDerPol
+            && model.compute_data.flow_view == FlowView::LimitCycle
+            && model.limit_cycle_data.get_mu() != mu
+        {
+            model.limit_cycle_data = LimitCycleData::for_mu(mu);
+            pipeline.update_limit_cycle_data_buffer(queue, &model.limit_cycle_data);
+        }
+
+        // Check if the orbit trap data buffer needs to be updated
+        if *model.update_orbit_trap_data_buffer.borrow() {
+            pipeline.update_orbit_trap_data_buffer(queue, &model.orbit_trap_data);
+            model.update_orbit_trap_data_buffer.replace(false);
+        }
+
         // Dispatch the compute pipeline
         let (width, height) = app.main_window().inner_size_pixels();
+        if accumulate {
+            pipeline.dispatch_density(&mut encoder, model.compute_data.num_particles, restart);
+        }
         pipeline.dispatch_compute(&mut encoder, queue, [width, height]);
 
         // Submit the command buffer
         queue.submit(Some(encoder.finish()));
 
+        // Start reading how many pixels the interior checks stopped early
+        if matches!(
+            model.compute_data.kernel,
+            Kernel::Mandelbrot | Kernel::Julia
+        ) {
+            pipeline.request_early_exits(model.compute_data.kernel);
+        }
+
         model.recompute_texture.replace(false);
     }
 
@@ -190,47 +489,598 @@ fn update(app: &App, model: &mut Model, update: Update) {
 fn update_egui(model: &mut Model, _app: &App) {
     let ctx = model.egui.begin_frame();
     let state = &mut model.state;
+    let mut selected_kernel = model.compute_data.kernel;
 
     // Generate the settings window
     egui::Window::new("Settings")
         .default_width(0.0)
         .show(&ctx, |ui| {
+            ui.label("Kernel:");
+            egui::ComboBox::from_id_source("kernel")
+                .selected_text(selected_kernel.name())
+                .show_ui(ui, |ui| {
+                    for kernel in Kernel::ALL {
+                        ui.selectable_value(&mut selected_kernel, kernel, kernel.name());
+                    }
+                });
+
+            ui.separator();
+
             ui.label("Zoom speed:");
             ui.add(egui::Slider::new(&mut state.zoom_speed, 0.0001..=0.1));
 
             ui.label("Shift speed:");
             ui.add(egui::Slider::new(&mut state.shift_speed, 10..=100));
 
-            ui.label("Max iterations:");
-            let old_max_iterations = model.compute_data.max_iter;
-            ui.add(egui::Slider::new(
-                &mut model.compute_data.max_iter,
-                200..=2000,
-            ));
-            if old_max_iterations != model.compute_data.max_iter {
-                model.update_compute_data_buffer.replace(true);
-                model.recompute_texture.replace(true);
+            let kernel = model.compute_data.kernel;
+
+            if kernel != Kernel::MathFn {
+                ui.label("Max iterations:");
+                let old_max_iterations = model.compute_data.max_iter;
+                if kernel == Kernel::DeepMandelbrot {
+                    // Deep zooms need many more iterations
+                    ui.add(
+                        egui::Slider::new(
+                            &mut model.compute_data.max_iter,
+                            200..=MAX_REFERENCE_ORBIT_LEN as u32 - 1,
+                        )
+                        .logarithmic(true),
+                    );
+                } else {
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.max_iter,
+                        200..=2000,
+                    ));
+                }
+                if old_max_iterations != model.compute_data.max_iter {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
             }
 
-            ui.label("dt:");
-            let old_dt = model.compute_data.dt;
-            ui.add(egui::Slider::new(&mut model.compute_data.dt, 0.01..=1.0));
-            if old_dt != model.compute_data.dt {
-                model.update_compute_data_buffer.replace(true);
-                model.recompute_texture.replace(true);
+            if kernel == Kernel::DeepMandelbrot {
+                let old_use_bla = state.use_bla;
+                ui.checkbox(&mut state.use_bla, "Bilinear Approximation");
+                if old_use_bla != state.use_bla {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+                if model.references.is_building() {
+                    ui.label("Computing the reference orbit...");
+                }
             }
 
-            ui.label("mu:");
-            let old_mu = model.compute_data.mu;
-            ui.add(egui::Slider::new(&mut model.compute_data.mu, 0.0..=10.0));
-            if old_mu != model.compute_data.mu {
-                model.update_compute_data_buffer.replace(true);
-                model.recompute_texture.replace(true);
+            if kernel.is_escape_time() {
+                ui.label("Coloring:");
+                let old_coloring = model.compute_data.get_coloring();
+                let mut coloring = old_coloring;
+                egui::ComboBox::from_id_source("coloring")
+                    .selected_text(coloring.name())
+                    .show_ui(ui, |ui| {
+                        for mode in Coloring::ALL {
+                            if mode.supports(kernel) {
+                                ui.selectable_value(&mut coloring, mode, mode.name());
+                            }
+                        }
+                    });
+                if old_coloring != coloring {
+                    model.compute_data.update_coloring(coloring);
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+
+                ui.label("Interior coloring:");
+                let old_interior_coloring = model.compute_data.interior_coloring;
+                egui::ComboBox::from_id_source("interior_coloring")
+                    .selected_text(old_interior_coloring.name())
+                    .show_ui(ui, |ui| {
+                        for mode in InteriorColoring::ALL {
+                            if mode.supports(kernel) {
+                                ui.selectable_value(
+                                    &mut model.compute_data.interior_coloring,
+                                    mode,
+                                    mode.name(),
+                                );
+                            }
+                        }
+                    });
+                if old_interior_coloring != model.compute_data.interior_coloring {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+
+                if coloring == Coloring::OrbitTrap {
+                    let trap = &mut model.orbit_trap_data;
+                    let old_trap = *trap;
+
+                    ui.label("Trap shape:");
+                    egui::ComboBox::from_id_source("trap_shape")
+                        .selected_text(trap.shape.name())
+                        .show_ui(ui, |ui| {
+                            for shape in TrapShape::ALL {
+                                ui.selectable_value(&mut trap.shape, shape, shape.name());
+                            }
+                        });
+
+                    // The image trap colors by the image where it is hit
+                    if trap.shape != TrapShape::Image {
+                        ui.label("Trap value:");
+                        egui::ComboBox::from_id_source("trap_value")
+                            .selected_text(trap.value.name())
+                            .show_ui(ui, |ui| {
+                                for value in TrapValue::ALL {
+                                    ui.selectable_value(&mut trap.value, value, value.name());
+                                }
+                            });
+                    }
+
+                    ui.label("Trap center:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut trap.center[0]).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut trap.center[1]).speed(0.01));
+                    });
+
+                    if matches!(trap.shape, TrapShape::Line | TrapShape::Cross) {
+                        ui.label("Trap angle:");
+                        ui.add(egui::Slider::new(&mut trap.angle, 0.0..=PI));
+                    }
+
+                    ui.label("Trap radius:");
+                    ui.add(egui::Slider::new(&mut trap.radius, 0.01..=4.0).logarithmic(true));
+
+                    if trap.shape == TrapShape::Image {
+                        ui.label("Trap image (PNG):");
+                        let response = ui.text_edit_singleline(&mut model.trap_image_path);
+                        if response.lost_focus() && !model.trap_image_path.is_empty() {
+                            model.pending_trap_image = Some(model.trap_image_path.clone());
+                        }
+                        if let Some(error) = &model.trap_image_error {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                    }
+
+                    if *trap != old_trap {
+                        model.update_orbit_trap_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+
+                if coloring == Coloring::Stripe {
+                    ui.label("Stripe frequency:");
+                    let old_frequency = model.compute_data.stripe_frequency;
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.stripe_frequency,
+                        1.0..=20.0,
+                    ));
+                    if old_frequency != model.compute_data.stripe_frequency {
+                        model.update_compute_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+
+                if matches!(coloring, Coloring::Stripe | Coloring::TriangleInequality) {
+                    ui.label("Color density:");
+                    let old_density = model.compute_data.color_density;
+                    ui.add(
+                        egui::Slider::new(&mut model.compute_data.color_density, 0.1..=10.0)
+                            .logarithmic(true),
+                    );
+                    if old_density != model.compute_data.color_density {
+                        model.update_compute_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+
+                if coloring == Coloring::Distance {
+                    ui.label("Boundary thickness (pixels):");
+                    let old_thickness = model.compute_data.thickness;
+                    ui.add(
+                        egui::Slider::new(&mut model.compute_data.thickness, 0.1..=10.0)
+                            .logarithmic(true),
+                    );
+                    if old_thickness != model.compute_data.thickness {
+                        model.update_compute_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+
+                ui.label("Bailout radius:");
+                let old_bailout = model.compute_data.bailout;
+                ui.add(
+                    egui::Slider::new(
+                        &mut model.compute_data.bailout,
+                        coloring.min_bailout()..=1000.0,
+                    )
+                    .logarithmic(true),
+                );
+                if old_bailout != model.compute_data.bailout {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if matches!(kernel, Kernel::Mandelbrot | Kernel::Julia) {
+                ui.label("Interior checks (early exits):");
+                for check in InteriorCheck::ALL {
+                    // The cardioid and the bulb are regions of the
+                    // Mandelbrot set only
+                    if kernel == Kernel::Julia && check != InteriorCheck::Periodicity {
+                        continue;
+                    }
+                    let mut enabled = model.compute_data.is_interior_check_enabled(check);
+                    let label = format!("{} ({})", check.name(), model.early_exits[check.index()]);
+                    if ui.checkbox(&mut enabled, label).changed() {
+                        model.compute_data.set_interior_check(check, enabled);
+                        model.update_compute_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+            }
+
+            if kernel == Kernel::Multibrot {
+                ui.label("Exponent:");
+                let old_exponent = model.compute_data.exponent;
+                ui.add(egui::Slider::new(
+                    &mut model.compute_data.exponent,
+                    1.0..=8.0,
+                ));
+                if old_exponent != model.compute_data.exponent {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if kernel == Kernel::Phoenix {
+                ui.label("Phoenix p:");
+                let old_phoenix_p = model.compute_data.phoenix_p;
+                ui.add(egui::Slider::new(
+                    &mut model.compute_data.phoenix_p,
+                    -1.0..=1.0,
+                ));
+                if old_phoenix_p != model.compute_data.phoenix_p {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if kernel == Kernel::Newton {
+                ui.label("Relaxation:");
+                let old_relaxation = model.newton_data.relaxation;
+                ui.add(egui::Slider::new(
+                    &mut model.newton_data.relaxation,
+                    0.1..=2.0,
+                ));
+                let mut changed = old_relaxation != model.newton_data.relaxation;
+
+                ui.label("Roots:");
+                let mut removed_root = None;
+                for (i, root) in model.newton_data.roots_mut().iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        let re = ui.add(
+                            egui::DragValue::new(&mut root[0])
+                                .speed(0.01)
+                                .prefix("re: "),
+                        );
+                        let im = ui.add(
+                            egui::DragValue::new(&mut root[1])
+                                .speed(0.01)
+                                .prefix("im: "),
+                        );
+                        changed |= re.changed() || im.changed();
+                        if ui.button("Remove").clicked() {
+                            removed_root = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed_root {
+                    model.newton_data.remove_root(i);
+                    changed = true;
+                }
+                if model.newton_data.roots().len() < MAX_NEWTON_ROOTS
+                    && ui.button("Add root").clicked()
+                {
+                    model.newton_data.add_root([0.0, 0.0]);
+                    changed = true;
+                }
+
+                if changed {
+                    model.update_newton_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if kernel == Kernel::Julia {
+                ui.label("Julia c (Ctrl+click in Mandelbrot):");
+                let (old_re, old_im) = model.compute_data.get_julia_c();
+                let (mut re, mut im) = (old_re, old_im);
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut re).speed(0.001).prefix("re: "));
+                    ui.add(egui::DragValue::new(&mut im).speed(0.001).prefix("im: "));
+                });
+                if (re, im) != (old_re, old_im) {
+                    model.compute_data.update_julia_c((re, im));
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if model.compute_data.accumulates_density() {
+                ui.label("Samples per compute:");
+                let old_num_particles = model.compute_data.num_particles;
+                ui.add(
+                    egui::Slider::new(&mut model.compute_data.num_particles, 1_000..=1_000_000)
+                        .logarithmic(true),
+                );
+                if old_num_particles != model.compute_data.num_particles {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+                ui.label("Enable Continuous Redraw to accumulate samples.");
+            }
+
+            if kernel == Kernel::Lyapunov {
+                ui.label("Sequence:");
+                let response = ui
+                    .text_edit_singleline(&mut model.lyapunov_sequence)
+                    .on_hover_text("Letters A and B, choosing r = x or r = y at each step");
+
+                // Update the sequence once it is entered
+                if response.lost_focus() {
+                    match model
+                        .lyapunov_data
+                        .update_sequence(&model.lyapunov_sequence)
+                    {
+                        Ok(()) => {
+                            model.lyapunov_error = None;
+                            model.update_lyapunov_data_buffer.replace(true);
+                            model.recompute_texture.replace(true);
+                        }
+                        Err(error) => model.lyapunov_error = Some(error),
+                    }
+                }
+
+                if let Some(error) = &model.lyapunov_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.label("Warmup iterations:");
+                let old_warmup = model.lyapunov_data.warmup;
+                ui.add(egui::Slider::new(&mut model.lyapunov_data.warmup, 0..=1000));
+                if old_warmup != model.lyapunov_data.warmup {
+                    model.update_lyapunov_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if let Some(kind) = kernel.formula_kind() {
+                ui.label("Formula:");
+                let source = &mut model.formula_sources[kind as usize];
+                let variables = kind
+                    .variables()
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let response = ui
+                    .text_edit_singleline(source)
+                    .on_hover_text(format!("Variables: {variables}"));
+
+                // Compile the formula once it is entered
+                if response.lost_focus() && source.as_str() != model.formulas.get(kind).source() {
+                    match Formula::parse(kind, source) {
+                        Ok(formula) => {
+                            let mut formulas = model.formulas.clone();
+                            formulas.set(formula);
+                            model.pending_formulas = Some(formulas);
+                            model.formula_error = None;
+                        }
+                        Err(error) => model.formula_error = Some(error.to_string()),
+                    }
+                }
+
+                if let Some(error) = &model.formula_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            }
+
+            if kernel.is_flow() {
+                ui.label("dt:");
+                let old_dt = model.compute_data.dt;
+                ui.add(egui::Slider::new(&mut model.compute_data.dt, 0.01..=1.0));
+                if old_dt != model.compute_data.dt {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if matches!(
+                kernel,
+                Kernel::VanDerPol | Kernel::FlowFormula | Kernel::ForcedVanDerPol
+            ) {
+                ui.label("mu:");
+                let old_mu = model.compute_data.mu;
+                ui.add(egui::Slider::new(&mut model.compute_data.mu, 0.0..=10.0));
+                if old_mu != model.compute_data.mu {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if kernel.is_flow() {
+                let old_integrator = model.compute_data.integrator;
+                let old_tolerance = model.compute_data.tolerance;
+
+                ui.label("Integrator:");
+                egui::ComboBox::from_id_source("integrator")
+                    .selected_text(old_integrator.name())
+                    .show_ui(ui, |ui| {
+                        for integrator in Integrator::ALL {
+                            ui.selectable_value(
+                                &mut model.compute_data.integrator,
+                                integrator,
+                                integrator.name(),
+                            );
+                        }
+                    });
+
+                // The adaptive integrator starts from dt and adapts it
+                if model.compute_data.integrator.is_adaptive() {
+                    ui.label("Tolerance:");
+                    ui.add(
+                        egui::Slider::new(&mut model.compute_data.tolerance, 1e-8..=1e-1)
+                            .logarithmic(true),
+                    );
+                }
+
+                if old_integrator != model.compute_data.integrator
+                    || old_tolerance != model.compute_data.tolerance
+                {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+
+                let old_flow_view = model.compute_data.flow_view;
+                let old_lic_length = model.compute_data.lic_length;
+                let old_lic_coloring = model.compute_data.lic_coloring;
+                let old_cycle_tolerance = model.compute_data.cycle_tolerance;
+                let old_section_phase = model.compute_data.section_phase;
+
+                ui.label("Flow view:");
+                egui::ComboBox::from_id_source("flow_view")
+                    .selected_text(old_flow_view.name())
+                    .show_ui(ui, |ui| {
+                        for view in FlowView::ALL {
+                            if view.supports(kernel) {
+                                ui.selectable_value(
+                                    &mut model.compute_data.flow_view,
+                                    view,
+                                    view.name(),
+                                );
+                            }
+                        }
+                    });
+
+                if model.compute_data.flow_view == FlowView::LineIntegralConvolution {
+                    ui.label("Streamline length:");
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.lic_length,
+                        1..=100,
+                    ));
+
+                    ui.label("LIC coloring:");
+                    egui::ComboBox::from_id_source("lic_coloring")
+                        .selected_text(old_lic_coloring.name())
+                        .show_ui(ui, |ui| {
+                            for coloring in LicColoring::ALL {
+                                ui.selectable_value(
+                                    &mut model.compute_data.lic_coloring,
+                                    coloring,
+                                    coloring.name(),
+                                );
+                            }
+                        });
+                }
+
+                if model.compute_data.flow_view == FlowView::LimitCycle {
+                    ui.label("Cycle tolerance:");
+                    ui.add(
+                        egui::Slider::new(&mut model.compute_data.cycle_tolerance, 0.001..=0.5)
+                            .logarithmic(true),
+                    );
+                }
+
+                if model.compute_data.flow_view == FlowView::PoincareSection {
+                    ui.label("Section phase:");
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.section_phase,
+                        0.0..=1.0,
+                    ));
+                }
+
+                // The Arnold tongues are drawn over the forcing instead of the
+                // phase plane, whose ranges are restored when leaving them
+                let flow_view = model.compute_data.flow_view;
+                if old_flow_view != flow_view
+                    && (old_flow_view == FlowView::ArnoldTongues
+                        || flow_view == FlowView::ArnoldTongues)
+                {
+                    let (x_range, y_range) = flow_view.initial_ranges(kernel);
+                    model.compute_data.update_x_range((x_range[0], x_range[1]));
+                    model.compute_data.update_y_range((y_range[0], y_range[1]));
+                }
+
+                // The forcing is set by the position of the pixels in the
+                // Arnold tongues
+                if kernel.is_forced() && flow_view != FlowView::ArnoldTongues {
+                    let old_forcing = (
+                        model.compute_data.forcing_amplitude,
+                        model.compute_data.forcing_frequency,
+                    );
+                    ui.label("Forcing amplitude:");
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.forcing_amplitude,
+                        0.0..=5.0,
+                    ));
+                    ui.label("Forcing frequency:");
+                    ui.add(egui::Slider::new(
+                        &mut model.compute_data.forcing_frequency,
+                        0.05..=5.0,
+                    ));
+                    if old_forcing
+                        != (
+                            model.compute_data.forcing_amplitude,
+                            model.compute_data.forcing_frequency,
+                        )
+                    {
+                        model.update_compute_data_buffer.replace(true);
+                        model.recompute_texture.replace(true);
+                    }
+                }
+
+                if old_flow_view != model.compute_data.flow_view
+                    || old_lic_length != model.compute_data.lic_length
+                    || old_lic_coloring != model.compute_data.lic_coloring
+                    || old_cycle_tolerance != model.compute_data.cycle_tolerance
+                    || old_section_phase != model.compute_data.section_phase
+                {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if !kernel.parameters().is_empty() {
+                let old_params = model.compute_data.params;
+                for ((name, range), param) in kernel
+                    .parameters()
+                    .iter()
+                    .zip(&mut model.compute_data.params)
+                {
+                    ui.label(format!("{name}:"));
+                    ui.add(egui::Slider::new(param, range.clone()));
+                }
+                if old_params != model.compute_data.params {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
+            }
+
+            if kernel == Kernel::Lorenz {
+                ui.label("dt:");
+                let old_dt = model.compute_data.dt;
+                ui.add(
+                    egui::Slider::new(&mut model.compute_data.dt, 0.001..=0.02).logarithmic(true),
+                );
+                if old_dt != model.compute_data.dt {
+                    model.update_compute_data_buffer.replace(true);
+                    model.recompute_texture.replace(true);
+                }
             }
 
             ui.separator();
 
             ui.checkbox(&mut state.continuous_compute, "Continuous Redraw");
+            ui.checkbox(&mut state.hot_reload, "Hot Reload Shaders")
+                .on_hover_text(SHADER_DIRECTORY);
 
             let old_post_processing = model.pipeline.borrow().enable_post_processing;
             ui.checkbox(
@@ -249,6 +1099,37 @@ fn update_egui(model: &mut Model, _app: &App) {
                 state.save_image = true;
             }
         });
+
+    // Show the errors of the reloaded shaders until they are fixed
+    if let Some(error) = &model.shader_error {
+        egui::TopBottomPanel::bottom("shader_errors").show(&ctx, |ui| {
+            ui.colored_label(
+                egui::Color32::RED,
+                "Shader errors, the last valid shaders are still used:",
+            );
+            ui.monospace(error);
+        });
+    }
+
+    // End the egui frame before modifying the model
+    drop(ctx);
+
+    if selected_kernel != model.compute_data.kernel {
+        switch_kernel(model, selected_kernel);
+    }
+}
+
+/// Switches the kernel used by the compute shader.
+///
+/// The compute data of the current kernel is remembered so that its view and
+/// parameters are restored the next time it is selected.
+fn switch_kernel(model: &mut Model, kernel: Kernel) {
+    let current_kernel = model.compute_data.kernel;
+    model.kernel_compute_data[current_kernel.index()] = model.compute_data;
+    model.compute_data = model.kernel_compute_data[kernel.index()];
+    model.formula_error = None;
+    model.update_compute_data_buffer.replace(true);
+    model.recompute_texture.replace(true);
 }
 
 fn resized(app: &App, model: &mut Model, _dim: Vec2) {
@@ -257,8 +1138,9 @@ fn resized(app: &App, model: &mut Model, _dim: Vec2) {
     let (width, height) = window.inner_size_pixels();
 
     // When the window size changes, recreate our texture to match and
-    // ask to recompute the image
+    // ask to recompute the image, restarting the accumulated samples
     model.pipeline.borrow_mut().resize(device, [width, height]);
+    model.update_compute_data_buffer.replace(true);
     model.recompute_texture.replace(true);
 }
 
@@ -268,63 +1150,22 @@ fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event:
 
 fn key_pressed(app: &App, model: &mut Model, key: Key) {
     let state = &mut model.state;
+    let shift_speed = 1.0 / state.shift_speed as FloatChoice;
 
     // When we shift or zoom, we need to update the data buffer and
     // ask to recompute the texture
     match key {
-        Key::Left => {
-            let current_x_range = model.compute_data.get_x_range();
-            let shift_x = get_shift_speed(current_x_range, state.shift_speed);
-            let new_x_range = shift(current_x_range, -shift_x);
-            model.compute_data.update_x_range(new_x_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
-        }
-        Key::Right => {
-            let current_x_range = model.compute_data.get_x_range();
-            let shift_x = get_shift_speed(current_x_range, state.shift_speed);
-            let new_x_range = shift(current_x_range, shift_x);
-            model.compute_data.update_x_range(new_x_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
-        }
-        Key::Up => {
-            let current_y_range = model.compute_data.get_y_range();
-            let shift_y = get_shift_speed(current_y_range, state.shift_speed);
-            let new_y_range = shift(current_y_range, shift_y);
-            model.compute_data.update_y_range(new_y_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
-        }
-        Key::Down => {
-            let current_y_range = model.compute_data.get_y_range();
-            let shift_y = get_shift_speed(current_y_range, state.shift_speed);
-            let new_y_range = shift(current_y_range, -shift_y);
-            model.compute_data.update_y_range(new_y_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
-        }
+        Key::Left => shift_view(model, (-shift_speed, 0.0)),
+        Key::Right => shift_view(model, (shift_speed, 0.0)),
+        Key::Up => shift_view(model, (0.0, shift_speed)),
+        Key::Down => shift_view(model, (0.0, -shift_speed)),
         Key::Plus | Key::Equals => {
             let zoom_factor = 1.0 - 10.0 * state.zoom_speed;
-            let current_x_range = model.compute_data.get_x_range();
-            let current_y_range = model.compute_data.get_y_range();
-            let (new_x_range, new_y_range) =
-                zoom_relative(current_x_range, current_y_range, zoom_factor, (0.5, 0.5));
-            model.compute_data.update_x_range(new_x_range);
-            model.compute_data.update_y_range(new_y_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
+            zoom_view(model, zoom_factor, (0.5, 0.5));
         }
         Key::Minus => {
             let zoom_factor = 1.0 + 10.0 * state.zoom_speed;
-            let current_x_range = model.compute_data.get_x_range();
-            let current_y_range = model.compute_data.get_y_range();
-            let (new_x_range, new_y_range) =
-                zoom_relative(current_x_range, current_y_range, zoom_factor, (0.5, 0.5));
-            model.compute_data.update_x_range(new_x_range);
-            model.compute_data.update_y_range(new_y_range);
-            model.update_compute_data_buffer.replace(true);
-            model.recompute_texture.replace(true);
+            zoom_view(model, zoom_factor, (0.5, 0.5));
         }
         Key::Q => app.quit(),
         Key::S => state.save_image = true,
@@ -335,8 +1176,6 @@ fn key_pressed(app: &App, model: &mut Model, key: Key) {
 
 fn mouse_wheel(_app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
     let state = &mut model.state;
-    let current_x_range = model.compute_data.get_x_range();
-    let current_y_range = model.compute_data.get_y_range();
 
     // Compute the zoom factor based on the mouse wheel delta
     let zoom_factor = match delta {
@@ -344,26 +1183,9 @@ fn mouse_wheel(_app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: T
         MouseScrollDelta::PixelDelta(pos) => 1.0 + pos.y as FloatChoice * state.zoom_speed,
     };
 
-    // Compute the new x/y ranges based on the zoom factor and mouse position
-    let (new_x_range, new_y_range) = zoom_relative(
-        current_x_range,
-        current_y_range,
-        zoom_factor,
-        state.mouse_pos,
-    );
-
-    // Make sure not to zoom too much to avoid numerical issues
-    if (new_x_range.1 - new_x_range.0).abs() < MAX_ZOOM_DELTA
-        || (new_y_range.1 - new_y_range.0).abs() < MAX_ZOOM_DELTA
-    {
-        return;
-    }
-
-    // Update the x/y ranges in the data buffer and recompute the texture
-    model.compute_data.update_x_range(new_x_range);
-    model.compute_data.update_y_range(new_y_range);
-    model.update_compute_data_buffer.replace(true);
-    model.recompute_texture.replace(true);
+    // Zoom based on the zoom factor and mouse position
+    let zoom_focus = state.mouse_pos;
+    zoom_view(model, zoom_factor, zoom_focus);
 }
 
 fn mouse_moved(app: &App, model: &mut Model, pos: Point2) {
@@ -383,32 +1205,108 @@ fn mouse_moved(app: &App, model: &mut Model, pos: Point2) {
         let dx = state.mouse_pos.0 - prev_x;
         let dy = state.mouse_pos.1 - prev_y;
 
-        // Get current ranges
+        // Remember this pos for the next delta
+        state.prev_drag_pos = state.mouse_pos;
+
+        // Apply shift to the viewport
+        shift_view(model, (-dx, -dy));
+    }
+}
+
+/// Zooms the view by a factor of `zoom_factor` centered at a relative
+/// `zoom_focus` point.
+///
+/// The zoom is ignored if it would go beyond the precision of the kernel.
+fn zoom_view(model: &mut Model, zoom_factor: FloatChoice, zoom_focus: (FloatChoice, FloatChoice)) {
+    if model.compute_data.kernel == Kernel::DeepMandelbrot {
+        // Make sure not to zoom beyond the range of f64
+        let (w, h) = model.deep_view.get_size();
+        let factor = to_f64(zoom_factor);
+        if w * factor < MAX_DEEP_ZOOM_DELTA || h * factor < MAX_DEEP_ZOOM_DELTA {
+            return;
+        }
+
+        let focus = (to_f64(zoom_focus.0), to_f64(zoom_focus.1));
+        model.deep_view.zoom_relative(factor, focus);
+        sync_deep_view(model);
+    } else {
+        let current_x_range = model.compute_data.get_x_range();
+        let current_y_range = model.compute_data.get_y_range();
+
+        // Compute the new x/y ranges based on the zoom factor and focus
+        let (new_x_range, new_y_range) =
+            zoom_relative(current_x_range, current_y_range, zoom_factor, zoom_focus);
+
+        // Make sure not to zoom too much to avoid numerical issues. The view
+        // can be below the limit after switching to a kernel or coloring
+        // with less precision, so zooming out is always allowed
+        let max_zoom_delta = model.compute_data.max_zoom_delta();
+        if zoom_factor < 1.0
+            && ((new_x_range.1 - new_x_range.0).abs() < max_zoom_delta
+                || (new_y_range.1 - new_y_range.0).abs() < max_zoom_delta)
+        {
+            return;
+        }
+
+        model.compute_data.update_x_range(new_x_range);
+        model.compute_data.update_y_range(new_y_range);
+    }
+
+    // Update the data buffer and recompute the texture
+    model.update_compute_data_buffer.replace(true);
+    model.recompute_texture.replace(true);
+}
+
+/// Shifts the view by an offset relative to its size.
+fn shift_view(model: &mut Model, offset: (FloatChoice, FloatChoice)) {
+    if model.compute_data.kernel == Kernel::DeepMandelbrot {
+        model
+            .deep_view
+            .shift_relative((to_f64(offset.0), to_f64(offset.1)));
+        sync_deep_view(model);
+    } else {
         let (x0, x1) = model.compute_data.get_x_range();
         let (y0, y1) = model.compute_data.get_y_range();
 
         // Compute how much to shift in "range units"
-        let range_w = x1 - x0;
-        let range_h = y1 - y0;
-        let shift_x = -dx * range_w;
-        let shift_y = -dy * range_h;
-
-        // Apply shift to the viewport
-        let new_x_range = shift((x0, x1), shift_x);
-        let new_y_range = shift((y0, y1), shift_y);
+        let new_x_range = shift((x0, x1), offset.0 * (x1 - x0));
+        let new_y_range = shift((y0, y1), offset.1 * (y1 - y0));
         model.compute_data.update_x_range(new_x_range);
         model.compute_data.update_y_range(new_y_range);
+    }
 
-        // Ask to update data buffer and recompute the texture
-        model.update_compute_data_buffer.replace(true);
-        model.recompute_texture.replace(true);
+    // Update the data buffer and recompute the texture
+    model.update_compute_data_buffer.replace(true);
+    model.recompute_texture.replace(true);
+}
 
-        // Remember this pos for the next delta
-        state.prev_drag_pos = state.mouse_pos;
-    }
+/// Updates the x/y ranges of the compute data to match the deep zoom view.
+///
+/// The ranges are only an approximation of the view, the perturbation kernel
+/// uses the reference orbit and the size of the view instead.
+fn sync_deep_view(model: &mut Model) {
+    let (x_range, y_range) = model.deep_view.get_ranges();
+    model
+        .compute_data
+        .update_x_range((x_range.0 as FloatChoice, x_range.1 as FloatChoice));
+    model
+        .compute_data
+        .update_y_range((y_range.0 as FloatChoice, y_range.1 as FloatChoice));
 }
 
-fn mouse_pressed(_app: &App, model: &mut Model, _button: MouseButton) {
+fn mouse_pressed(app: &App, model: &mut Model, _button: MouseButton) {
+    // Ctrl+click in the Mandelbrot set picks the seed of the Julia set
+    if app.keys.mods.ctrl() && model.compute_data.kernel == Kernel::Mandelbrot {
+        let (x, y) = model.state.mouse_pos;
+        let c = (
+            map(x, (0.0, 1.0), model.compute_data.get_x_range()),
+            map(y, (0.0, 1.0), model.compute_data.get_y_range()),
+        );
+        model.kernel_compute_data[Kernel::Julia.index()].update_julia_c(c);
+        switch_kernel(model, Kernel::Julia);
+        return;
+    }
+
     let state = &mut model.state;
 
     // Start a mouse drag
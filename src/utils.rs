@@ -1,4 +1,18 @@
+pub mod bla;
+pub mod coloring;
+pub mod double_single;
 pub mod faraday;
+pub mod flow;
+pub mod formula;
+pub mod integrator;
+pub mod interior;
+pub mod kernels;
+pub mod limit_cycle;
 pub mod math;
+pub mod orbit_trap;
+pub mod perturbation;
 pub mod pipeline;
 pub mod pipeline_buffers;
+pub mod preprocessor;
+pub mod reference;
+pub mod shader_watcher;
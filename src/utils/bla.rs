@@ -0,0 +1,323 @@
+use std::f64::consts::LN_2;
+
+use super::perturbation::exp2i;
+
+/// Precision of the bilinear approximation, matching the precision of the
+/// `f32` deltas iterated by the perturbation kernel.
+const BLA_EPSILON_LOG2: f64 = -24.0;
+
+/// Bilinear approximation of a block of iterations of the perturbation
+/// formula dz -> 2 Z dz + dz^2 + dc.
+///
+/// Skipping the block maps dz to A dz + B dc, which is valid as long as |dz|
+/// is smaller than the radius of the step. The coefficients A and B are
+/// stored as `f32` mantissas with a power of two exponent so that they do not
+/// overflow, even for long blocks.
+///
+/// This struct is shared with the compute shader and must match its layout.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlaStep {
+    a: [f32; 2],
+    b: [f32; 2],
+    a_exponent: i32,
+    b_exponent: i32,
+    log2_radius: f32,
+    _padding: u32,
+}
+
+impl BlaStep {
+    /// Returns the base-2 logarithm of the validity radius of the step.
+    pub fn log2_radius(&self) -> f32 {
+        self.log2_radius
+    }
+
+    /// Applies the step to a delta dz of a point offset by dc from the
+    /// reference, returning A dz + B dc.
+    pub fn apply(&self, dz: (f64, f64), dc: (f64, f64)) -> (f64, f64) {
+        let a = (
+            scale(self.a[0] as f64, self.a_exponent),
+            scale(self.a[1] as f64, self.a_exponent),
+        );
+        let b = (
+            scale(self.b[0] as f64, self.b_exponent),
+            scale(self.b[1] as f64, self.b_exponent),
+        );
+        (
+            a.0 * dz.0 - a.1 * dz.1 + b.0 * dc.0 - b.1 * dc.1,
+            a.0 * dz.1 + a.1 * dz.0 + b.0 * dc.1 + b.1 * dc.0,
+        )
+    }
+
+    /// Applies the step to a delta dz = w 2^k of a point offset by
+    /// dc = dc_mantissa 2^dc_exponent from the reference, like the compute
+    /// shader does.
+    ///
+    /// # Returns
+    ///
+    /// - A dz + B dc, as a mantissa and a power of two exponent, with the
+    ///   largest of both terms used as scale.
+    pub fn apply_scaled(&self, dz: ([f32; 2], i32), dc: ([f32; 2], i32)) -> ([f32; 2], i32) {
+        let ((w, k), (dc, dc_exponent)) = (dz, dc);
+        let ea = k + self.a_exponent;
+        let eb = dc_exponent + self.b_exponent;
+        let e = ea.max(eb);
+        let (sa, sb) = (exp2i(ea - e), exp2i(eb - e));
+        let aw = [
+            self.a[0] * w[0] - self.a[1] * w[1],
+            self.a[0] * w[1] + self.a[1] * w[0],
+        ];
+        let bdc = [
+            self.b[0] * dc[0] - self.b[1] * dc[1],
+            self.b[0] * dc[1] + self.b[1] * dc[0],
+        ];
+        ([aw[0] * sa + bdc[0] * sb, aw[1] * sa + bdc[1] * sb], e)
+    }
+
+    fn from_scaled(a: Scaled, b: Scaled, log2_radius: f64) -> Self {
+        Self {
+            a: [a.re as f32, a.im as f32],
+            b: [b.re as f32, b.im as f32],
+            a_exponent: a.exponent,
+            b_exponent: b.exponent,
+            // Keep the radius finite so that comparisons behave on the GPU
+            log2_radius: log2_radius.max(f32::MIN as f64) as f32,
+            _padding: 0,
+        }
+    }
+
+    fn a(&self) -> Scaled {
+        Scaled::new(self.a[0] as f64, self.a[1] as f64, self.a_exponent)
+    }
+
+    fn b(&self) -> Scaled {
+        Scaled::new(self.b[0] as f64, self.b[1] as f64, self.b_exponent)
+    }
+}
+
+/// Table of bilinear approximations (BLA) built from a reference orbit.
+///
+/// Level 0 holds one step per iteration of the reference orbit, starting at
+/// iteration 1. Each step of level `l + 1` merges two consecutive steps of
+/// level `l`, and therefore skips `2^l` iterations. The levels are stored one
+/// after the other, in the order expected by the compute shader.
+#[derive(Clone, Debug, Default)]
+pub struct BlaTable {
+    steps: Vec<BlaStep>,
+    levels: u32,
+    reference_len: usize,
+}
+
+impl BlaTable {
+    /// Builds the table for a reference orbit.
+    ///
+    /// # Arguments
+    ///
+    /// - `reference_orbit`: The reference orbit, starting at z = 0.
+    /// - `max_dc`: The largest distance between a pixel and the reference
+    ///   point, used to bound the error of merged steps.
+    pub fn new(reference_orbit: &[[f32; 2]], max_dc: f64) -> Self {
+        let reference_len = reference_orbit.len();
+        let log2_max_dc = max_dc.abs().log2();
+
+        // Single iteration from Z_m: A = 2 Z_m, B = 1, valid while the dropped
+        // dz^2 term is negligible compared to Z_m dz
+        let mut level: Vec<BlaStep> = reference_orbit
+            .iter()
+            .take(reference_len.saturating_sub(1))
+            .skip(1)
+            .map(|z| {
+                let (zr, zi) = (z[0] as f64, z[1] as f64);
+                let a = Scaled::new(2.0 * zr, 2.0 * zi, 0);
+                let b = Scaled::new(1.0, 0.0, 0);
+                let log2_radius = zr.hypot(zi).log2() + BLA_EPSILON_LOG2;
+                BlaStep::from_scaled(a, b, log2_radius)
+            })
+            .collect();
+
+        let mut steps = Vec::with_capacity(2 * level.len());
+        let mut levels = 0;
+        while !level.is_empty() {
+            steps.extend_from_slice(&level);
+            levels += 1;
+            level = level
+                .chunks_exact(2)
+                .map(|pair| merge(&pair[0], &pair[1], log2_max_dc))
+                .collect();
+        }
+
+        Self {
+            steps,
+            levels,
+            reference_len,
+        }
+    }
+
+    /// Returns the steps of all the levels, one level after the other.
+    pub fn steps(&self) -> &[BlaStep] {
+        &self.steps
+    }
+
+    /// Returns the number of levels in the table.
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+
+    /// Finds the longest step starting at iteration `m` of the reference orbit
+    /// that is valid for a delta of magnitude `2^log2_dz`.
+    ///
+    /// This mirrors the lookup done by the compute shader.
+    ///
+    /// # Arguments
+    ///
+    /// - `m`: The current index in the reference orbit.
+    /// - `log2_dz`: The base-2 logarithm of |dz|.
+    /// - `max_skip`: The maximum number of iterations that can be skipped.
+    ///
+    /// # Returns
+    ///
+    /// - The step and the number of iterations it skips, if any is valid.
+    pub fn lookup(&self, m: usize, log2_dz: f32, max_skip: u32) -> Option<(&BlaStep, u32)> {
+        if m == 0 {
+            return None;
+        }
+
+        let mut found = None;
+        let mut offset = 0;
+        let mut count = self.reference_len.saturating_sub(2);
+        let mut index = m - 1;
+        for level in 0..self.levels {
+            let skip = 1 << level;
+            if index >= count || skip > max_skip {
+                break;
+            }
+
+            let step = &self.steps[offset + index];
+            if log2_dz >= step.log2_radius {
+                break;
+            }
+            found = Some((step, skip));
+
+            // A longer step only starts at every other step of this level
+            if !index.is_multiple_of(2) {
+                break;
+            }
+            offset += count;
+            count /= 2;
+            index /= 2;
+        }
+
+        found
+    }
+}
+
+/// Merges the step `x` with the step `y` that follows it.
+fn merge(x: &BlaStep, y: &BlaStep, log2_max_dc: f64) -> BlaStep {
+    let (ax, bx) = (x.a(), x.b());
+    let (ay, by) = (y.a(), y.b());
+
+    // A = Ay Ax, B = Ay Bx + By
+    let a = ay.mul(ax);
+    let b = ay.mul(bx).add(by);
+
+    // The step y is valid while |Ax dz + Bx dc| < ry, which holds when
+    // |dz| < (ry - |Bx| max|dc|) / |Ax|
+    let log2_ry = log2_sub(y.log2_radius as f64, bx.log2_norm() + log2_max_dc);
+    let log2_ax = ax.log2_norm();
+    let log2_radius = if log2_ax.is_finite() {
+        (x.log2_radius as f64).min(log2_ry - log2_ax)
+    } else {
+        f64::NEG_INFINITY
+    };
+
+    BlaStep::from_scaled(a, b, log2_radius)
+}
+
+/// Returns log2(2^a - 2^b), or negative infinity if the difference is not
+/// positive.
+fn log2_sub(a: f64, b: f64) -> f64 {
+    if b >= a {
+        f64::NEG_INFINITY
+    } else {
+        a + (-(b - a).exp2()).ln_1p() / LN_2
+    }
+}
+
+/// Returns `x * 2^exponent` without overflowing the intermediate power of two.
+fn scale(x: f64, exponent: i32) -> f64 {
+    let half = exponent / 2;
+    x * 2.0_f64.powi(half) * 2.0_f64.powi(exponent - half)
+}
+
+/// Complex number stored as a mantissa and a power of two exponent.
+#[derive(Clone, Copy, Debug)]
+struct Scaled {
+    re: f64,
+    im: f64,
+    exponent: i32,
+}
+
+impl Scaled {
+    /// Creates a new number, normalizing its mantissa to be close to 1.
+    fn new(re: f64, im: f64, exponent: i32) -> Self {
+        let largest = re.abs().max(im.abs());
+        if largest == 0.0 {
+            return Self::zero();
+        }
+
+        let shift = largest.log2().floor() as i32;
+        let factor = 2.0_f64.powi(-shift);
+        Self {
+            re: re * factor,
+            im: im * factor,
+            exponent: exponent + shift,
+        }
+    }
+
+    fn zero() -> Self {
+        Self {
+            re: 0.0,
+            im: 0.0,
+            exponent: 0,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+
+    fn mul(self, other: Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+            self.exponent + other.exponent,
+        )
+    }
+
+    fn add(self, other: Self) -> Self {
+        if self.is_zero() {
+            return other;
+        } else if other.is_zero() {
+            return self;
+        }
+
+        let exponent = self.exponent.max(other.exponent);
+        let (sa, sb) = (
+            scale(1.0, self.exponent - exponent),
+            scale(1.0, other.exponent - exponent),
+        );
+        Self::new(
+            self.re * sa + other.re * sb,
+            self.im * sa + other.im * sb,
+            exponent,
+        )
+    }
+
+    /// Returns log2 of the modulus of the number.
+    fn log2_norm(self) -> f64 {
+        self.re.hypot(self.im).log2() + self.exponent as f64
+    }
+}
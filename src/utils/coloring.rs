@@ -0,0 +1,168 @@
+use super::kernels::Kernel;
+
+/// Coloring modes of the escape-time kernels.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`Coloring::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coloring {
+    /// Colors by the number of iterations before escaping, which shows bands.
+    Banded = 0,
+    /// Colors by a continuous iteration count interpolated from the final
+    /// |z|, which removes the bands.
+    Smooth = 1,
+    /// Darkens the pixels by their distance to the boundary of the set,
+    /// estimated from the derivative of their orbit.
+    Distance = 2,
+    /// Colors by the distance of the orbit to a trap, following the trap
+    /// shape and value of the orbit trap data.
+    OrbitTrap = 3,
+    /// Colors by the average of the stripes sin(frequency arg z) along the
+    /// orbit, interpolated with the continuous iteration count.
+    Stripe = 4,
+    /// Colors by the average position of |z| between the bounds given by the
+    /// triangle inequality at each iteration, interpolated with the
+    /// continuous iteration count.
+    TriangleInequality = 5,
+}
+
+impl Coloring {
+    /// All the coloring modes, in the order they are displayed in the UI.
+    pub const ALL: [Coloring; 6] = [
+        Coloring::Banded,
+        Coloring::Smooth,
+        Coloring::Distance,
+        Coloring::OrbitTrap,
+        Coloring::Stripe,
+        Coloring::TriangleInequality,
+    ];
+
+    /// Returns the name of the coloring mode as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coloring::Banded => "Banded",
+            Coloring::Smooth => "Smooth",
+            Coloring::Distance => "Distance estimation",
+            Coloring::OrbitTrap => "Orbit trap",
+            Coloring::Stripe => "Stripe average",
+            Coloring::TriangleInequality => "Triangle inequality average",
+        }
+    }
+
+    /// Returns the name of the constant identifying the coloring mode in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            Coloring::Banded => "COLORING_BANDED",
+            Coloring::Smooth => "COLORING_SMOOTH",
+            Coloring::Distance => "COLORING_DISTANCE",
+            Coloring::OrbitTrap => "COLORING_ORBIT_TRAP",
+            Coloring::Stripe => "COLORING_STRIPE",
+            Coloring::TriangleInequality => "COLORING_TRIANGLE_INEQUALITY",
+        }
+    }
+
+    /// Returns the smallest bailout radius for the coloring mode.
+    ///
+    /// The continuous iteration count, the distance estimate and the
+    /// averages interpolated with the former assume that z^2 dominates c
+    /// once the orbit escapes, which only holds for large radii.
+    pub fn min_bailout(&self) -> f32 {
+        match self {
+            Coloring::Banded | Coloring::OrbitTrap => 2.0,
+            Coloring::Smooth
+            | Coloring::Distance
+            | Coloring::Stripe
+            | Coloring::TriangleInequality => 256.0,
+        }
+    }
+
+    /// Returns whether the coloring mode is available for the kernel.
+    ///
+    /// Distance estimation needs the derivative of the orbit, which is only
+    /// tracked for the quadratic Mandelbrot and Julia sets, like the averages
+    /// which are accumulated in their escape loop. Orbit traps need
+    /// the orbit itself, which the perturbation kernel only knows relative
+    /// to its reference.
+    pub fn supports(&self, kernel: Kernel) -> bool {
+        match self {
+            Coloring::Banded | Coloring::Smooth => kernel.is_escape_time(),
+            Coloring::Distance | Coloring::Stripe | Coloring::TriangleInequality => {
+                matches!(kernel, Kernel::Mandelbrot | Kernel::Julia)
+            }
+            Coloring::OrbitTrap => kernel.is_escape_time() && kernel != Kernel::DeepMandelbrot,
+        }
+    }
+}
+
+/// Coloring modes of the points inside the escape-time sets, which never
+/// escape.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`InteriorColoring::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteriorColoring {
+    /// Leaves the interior black.
+    Black = 0,
+    /// Colors by the period of the attracting cycle of the orbit, which is
+    /// the same in each hyperbolic component.
+    Period = 1,
+    /// Colors by the magnitude of the multiplier of the attracting cycle,
+    /// which is 0 at the center of each hyperbolic component and 1 on its
+    /// boundary.
+    Multiplier = 2,
+    /// Colors by the argument of the last point of the orbit.
+    Angle = 3,
+}
+
+impl InteriorColoring {
+    /// All the interior coloring modes, in the order they are displayed in
+    /// the UI.
+    pub const ALL: [InteriorColoring; 4] = [
+        InteriorColoring::Black,
+        InteriorColoring::Period,
+        InteriorColoring::Multiplier,
+        InteriorColoring::Angle,
+    ];
+
+    /// Returns the name of the interior coloring mode as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            InteriorColoring::Black => "Black",
+            InteriorColoring::Period => "Period",
+            InteriorColoring::Multiplier => "Multiplier",
+            InteriorColoring::Angle => "Final angle",
+        }
+    }
+
+    /// Returns the name of the constant identifying the interior coloring
+    /// mode in the shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            InteriorColoring::Black => "INTERIOR_COLORING_BLACK",
+            InteriorColoring::Period => "INTERIOR_COLORING_PERIOD",
+            InteriorColoring::Multiplier => "INTERIOR_COLORING_MULTIPLIER",
+            InteriorColoring::Angle => "INTERIOR_COLORING_ANGLE",
+        }
+    }
+
+    /// Returns whether the interior coloring mode is available for the
+    /// kernel.
+    ///
+    /// The period and the multiplier are found by the cycle detection of the
+    /// quadratic Mandelbrot and Julia sets.
+    pub fn supports(&self, kernel: Kernel) -> bool {
+        match self {
+            InteriorColoring::Black | InteriorColoring::Angle => kernel.is_escape_time(),
+            InteriorColoring::Period | InteriorColoring::Multiplier => {
+                matches!(kernel, Kernel::Mandelbrot | Kernel::Julia)
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{FloatChoice, ShaderFloat, to_f64};
+
+/// Double-single number, stored as `[hi, lo]` with |lo| at most half an ulp of
+/// hi, which represents the unevaluated sum hi + lo.
+///
+/// The functions of this module mirror the double-single arithmetic of the
+/// compute shader, so that its precision can be checked on the CPU.
+pub type DoubleSingle = [f32; 2];
+
+/// Splits a value into its nearest shader float and the remaining low part,
+/// as used by double-single arithmetic.
+///
+/// The low part is always 0 unless the shader float has less precision than
+/// [`FloatChoice`].
+pub fn split_double_single(value: FloatChoice) -> (ShaderFloat, f32) {
+    let hi = value as ShaderFloat;
+    let lo = (to_f64(value) - to_f64(hi as FloatChoice)) as f32;
+    (hi, lo)
+}
+
+/// Joins a value split by [`split_double_single`].
+pub fn join_double_single(hi: ShaderFloat, lo: f32) -> FloatChoice {
+    (to_f64(hi as FloatChoice) + lo as f64) as FloatChoice
+}
+
+/// Returns the sum of a and b as a double-single number, exactly.
+fn two_sum(a: f32, b: f32) -> DoubleSingle {
+    let s = a + b;
+    let bb = s - a;
+    let err = (a - (s - bb)) + (b - bb);
+    [s, err]
+}
+
+/// Same as [`two_sum`], assuming |a| >= |b|.
+fn quick_two_sum(a: f32, b: f32) -> DoubleSingle {
+    let s = a + b;
+    let err = b - (s - a);
+    [s, err]
+}
+
+/// Splits a into two halves of 12 bits each, such that a = hi + lo.
+fn split(a: f32) -> DoubleSingle {
+    let t = 4097.0 * a;
+    let hi = t - (t - a);
+    [hi, a - hi]
+}
+
+/// Returns the product of a and b as a double-single number, exactly.
+fn two_prod(a: f32, b: f32) -> DoubleSingle {
+    let p = a * b;
+    let sa = split(a);
+    let sb = split(b);
+    let err = ((sa[0] * sb[0] - p) + sa[0] * sb[1] + sa[1] * sb[0]) + sa[1] * sb[1];
+    [p, err]
+}
+
+/// Adds two double-single numbers, mirroring `ds_add` in the compute shader.
+pub fn ds_add(a: DoubleSingle, b: DoubleSingle) -> DoubleSingle {
+    let mut s = two_sum(a[0], b[0]);
+    let t = two_sum(a[1], b[1]);
+    s[1] += t[0];
+    s = quick_two_sum(s[0], s[1]);
+    s[1] += t[1];
+    quick_two_sum(s[0], s[1])
+}
+
+/// Subtracts two double-single numbers, mirroring `ds_sub` in the compute
+/// shader.
+pub fn ds_sub(a: DoubleSingle, b: DoubleSingle) -> DoubleSingle {
+    ds_add(a, [-b[0], -b[1]])
+}
+
+/// Multiplies two double-single numbers, mirroring `ds_mul` in the compute
+/// shader.
+pub fn ds_mul(a: DoubleSingle, b: DoubleSingle) -> DoubleSingle {
+    let mut p = two_prod(a[0], b[0]);
+    p[1] += a[0] * b[1] + a[1] * b[0];
+    quick_two_sum(p[0], p[1])
+}
@@ -0,0 +1,159 @@
+use super::kernels::Kernel;
+use crate::FloatChoice;
+
+/// Largest number of forcing periods after which the samples of a trajectory
+/// are compared to detect that it locks to the forcing.
+pub const MAX_LOCKING_PERIOD: u32 = 8;
+/// Distance under which two samples of a trajectory, relative to their
+/// magnitude, are considered equal when detecting that it locks to the
+/// forcing.
+pub const LOCKING_TOLERANCE: f32 = 1e-3;
+/// Ranges of the forcing frequency along x and of the forcing amplitude along
+/// y shown by the Arnold tongues view when it is selected.
+const ARNOLD_TONGUE_RANGES: ([FloatChoice; 2], [FloatChoice; 2]) = ([0.2, 3.2], [0.0, 1.5]);
+
+/// Rendering modes of the flow kernels, which integrate a 2D vector field.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`FlowView::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowView {
+    /// Colors by the number of steps before the trajectory of the pixel
+    /// leaves a disk, which is black for the trajectories which stay in it.
+    Escape = 0,
+    /// Convolves white noise along the streamlines of the field through each
+    /// pixel, which draws the whole phase portrait.
+    LineIntegralConvolution = 1,
+    /// Colors by the fixed point the trajectory of the pixel settles on, with
+    /// a hue per basin of attraction, darkened by the time it takes to
+    /// settle.
+    Basins = 2,
+    /// Colors by the phase of the limit cycle of the Van der Pol oscillator
+    /// that the trajectory of the pixel converges to, which is constant
+    /// along its isochrons, darkened by the time it takes to come within the
+    /// tolerance of the cycle.
+    LimitCycle = 3,
+    /// Maps the screen to the frequency and the amplitude of the forcing of
+    /// the forced oscillators, and colors by the ratio of forcing periods to
+    /// turns of the oscillator that the trajectory from (1, 0) locks to, up
+    /// to [`MAX_LOCKING_PERIOD`] periods, which draws the Arnold tongues.
+    /// The trajectories which do not lock are shaded in gray by their
+    /// rotation number.
+    ArnoldTongues = 4,
+    /// Integrates the forced oscillators from random points, and accumulates
+    /// their samples at a fixed phase of each forcing period into a density,
+    /// which draws the Poincaré section of their attractors.
+    PoincareSection = 5,
+}
+
+impl FlowView {
+    /// All the flow views, in the order they are displayed in the UI.
+    pub const ALL: [FlowView; 6] = [
+        FlowView::Escape,
+        FlowView::LineIntegralConvolution,
+        FlowView::Basins,
+        FlowView::LimitCycle,
+        FlowView::ArnoldTongues,
+        FlowView::PoincareSection,
+    ];
+
+    /// Returns the name of the flow view as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowView::Escape => "Escape time",
+            FlowView::LineIntegralConvolution => "Line integral convolution",
+            FlowView::Basins => "Basins of attraction",
+            FlowView::LimitCycle => "Limit cycle phase",
+            FlowView::ArnoldTongues => "Arnold tongues",
+            FlowView::PoincareSection => "Poincaré section",
+        }
+    }
+
+    /// Returns the name of the constant identifying the flow view in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            FlowView::Escape => "FLOW_VIEW_ESCAPE",
+            FlowView::LineIntegralConvolution => "FLOW_VIEW_LIC",
+            FlowView::Basins => "FLOW_VIEW_BASINS",
+            FlowView::LimitCycle => "FLOW_VIEW_LIMIT_CYCLE",
+            FlowView::ArnoldTongues => "FLOW_VIEW_ARNOLD_TONGUES",
+            FlowView::PoincareSection => "FLOW_VIEW_POINCARE_SECTION",
+        }
+    }
+
+    /// Returns whether the flow view is available for the kernel.
+    ///
+    /// The limit cycle is only computed for the Van der Pol oscillator, and
+    /// the forced oscillators settle on no fixed point. The line integral
+    /// convolution of the forced oscillators shows their unforced field.
+    pub fn supports(&self, kernel: Kernel) -> bool {
+        match self {
+            FlowView::Escape | FlowView::LineIntegralConvolution => kernel.is_flow(),
+            FlowView::Basins => kernel.is_flow() && !kernel.is_forced(),
+            FlowView::LimitCycle => kernel == Kernel::VanDerPol,
+            FlowView::ArnoldTongues | FlowView::PoincareSection => kernel.is_forced(),
+        }
+    }
+
+    /// Returns the initial x and y ranges of the flow view for the kernel,
+    /// which are those of the forcing for the Arnold tongues and those of the
+    /// phase plane otherwise.
+    pub fn initial_ranges(&self, kernel: Kernel) -> ([FloatChoice; 2], [FloatChoice; 2]) {
+        match self {
+            FlowView::ArnoldTongues => ARNOLD_TONGUE_RANGES,
+            _ => kernel.initial_ranges(),
+        }
+    }
+}
+
+/// Colorings of the line integral convolution, which modulate the convolved
+/// noise.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`LicColoring::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LicColoring {
+    /// Leaves the convolved noise gray.
+    Gray = 0,
+    /// Colors by the magnitude of the field, from blue where the flow is slow
+    /// to red where it is fast.
+    Speed = 1,
+    /// Colors by the divergence of the field, in red where the flow expands
+    /// and in blue where it contracts.
+    Divergence = 2,
+}
+
+impl LicColoring {
+    /// All the colorings, in the order they are displayed in the UI.
+    pub const ALL: [LicColoring; 3] = [
+        LicColoring::Gray,
+        LicColoring::Speed,
+        LicColoring::Divergence,
+    ];
+
+    /// Returns the name of the coloring as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LicColoring::Gray => "Gray",
+            LicColoring::Speed => "Speed",
+            LicColoring::Divergence => "Divergence",
+        }
+    }
+
+    /// Returns the name of the constant identifying the coloring in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            LicColoring::Gray => "LIC_COLORING_GRAY",
+            LicColoring::Speed => "LIC_COLORING_SPEED",
+            LicColoring::Divergence => "LIC_COLORING_DIVERGENCE",
+        }
+    }
+}
@@ -0,0 +1,911 @@
+use std::fmt;
+
+use num::complex::Complex64;
+
+/// Kind of formula typed by the user, which decides its variables and the
+/// values it must assign.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormulaKind {
+    /// Complex map `z = f(z, c)` iterated by the formula kernel from z = c.
+    EscapeTime,
+    /// Real 2D flow `dx = f(x, y, mu); dy = g(x, y, mu)`.
+    Flow,
+    /// Real function `y = f(x)` drawn by the math function kernel.
+    Function,
+}
+
+impl FormulaKind {
+    /// All the kinds of formulas.
+    pub const ALL: [FormulaKind; 3] = [
+        FormulaKind::EscapeTime,
+        FormulaKind::Flow,
+        FormulaKind::Function,
+    ];
+
+    /// Returns the formula used until the user types another one.
+    pub fn default_source(&self) -> &'static str {
+        match self {
+            FormulaKind::EscapeTime => "z^2 + c",
+            FormulaKind::Flow => "dx = y; dy = mu*(1 - x^2)*y - x",
+            FormulaKind::Function => "y = -x*cos(exp(sin(10x))*x)",
+        }
+    }
+
+    /// Returns the variables available in the formula, in the order of the
+    /// parameters of the generated WGSL function.
+    pub fn variables(&self) -> &'static [(&'static str, Type)] {
+        match self {
+            FormulaKind::EscapeTime => &[("z", Type::Complex), ("c", Type::Complex)],
+            FormulaKind::Flow => &[("x", Type::Real), ("y", Type::Real), ("mu", Type::Real)],
+            FormulaKind::Function => &[("x", Type::Real)],
+        }
+    }
+
+    /// Returns the values the formula must assign, in the order they are
+    /// returned.
+    pub fn outputs(&self) -> &'static [(&'static str, Type)] {
+        match self {
+            FormulaKind::EscapeTime => &[("z", Type::Complex)],
+            FormulaKind::Flow => &[("dx", Type::Real), ("dy", Type::Real)],
+            FormulaKind::Function => &[("y", Type::Real)],
+        }
+    }
+
+    /// Returns the name of the WGSL function generated for the formula.
+    pub fn wgsl_function(&self) -> &'static str {
+        match self {
+            FormulaKind::EscapeTime => "formula_escape_time",
+            FormulaKind::Flow => "formula_flow",
+            FormulaKind::Function => "formula_function",
+        }
+    }
+}
+
+/// Type of an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Real,
+    Complex,
+}
+
+impl Type {
+    /// Returns the WGSL type of the expression.
+    fn wgsl(&self) -> &'static str {
+        match self {
+            Type::Real => "f32",
+            Type::Complex => "vec2<f32>",
+        }
+    }
+}
+
+/// Error found while parsing or type checking a formula.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormulaError {
+    /// Column of the error in the formula, starting at 1.
+    column: usize,
+    message: String,
+}
+
+impl FormulaError {
+    /// Returns the column of the error in the formula, starting at 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+/// Formula typed by the user, parsed and type checked.
+///
+/// A formula is a list of assignments separated by `;`, such as
+/// `dx = y; dy = -x`. The target of the assignment can be omitted if the
+/// formula only assigns a single value, such as `z^3 + c*sin(z)`.
+///
+/// Expressions support `+`, `-`, `*`, `/`, `^`, implicit multiplication
+/// (`10x`, `2(x + 1)`), the constants `pi`, `e` and `i`, and the functions
+/// `sin`, `cos`, `tan`, `sinh`, `cosh`, `tanh`, `exp`, `log`, `sqrt`, `abs`,
+/// `arg`, `re`, `im` and `conj`. Real values are promoted to complex values
+/// when needed.
+#[derive(Clone, Debug)]
+pub struct Formula {
+    kind: FormulaKind,
+    source: String,
+    /// Expressions of the outputs, in the order of [`FormulaKind::outputs`].
+    outputs: Vec<Expr>,
+}
+
+impl Formula {
+    /// Parses and type checks a formula.
+    pub fn parse(kind: FormulaKind, source: &str) -> Result<Self, FormulaError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            kind,
+            tokens: &tokens,
+            position: 0,
+            end_column: source.chars().count() + 1,
+        };
+        let assignments = parser.parse_assignments()?;
+
+        // Match each assignment with an output of the formula
+        let expected = kind.outputs();
+        let mut outputs: Vec<Option<Expr>> = vec![None; expected.len()];
+        for (target, column, expr) in assignments {
+            let index = match target {
+                None if expected.len() == 1 => 0,
+                None => {
+                    return Err(FormulaError {
+                        column,
+                        message: format!("expected an assignment to {}", output_names(kind)),
+                    });
+                }
+                Some(target) => match expected.iter().position(|(name, _)| *name == target) {
+                    Some(index) => index,
+                    None => {
+                        return Err(FormulaError {
+                            column,
+                            message: format!(
+                                "cannot assign {target}, expected {}",
+                                output_names(kind)
+                            ),
+                        });
+                    }
+                },
+            };
+
+            let (name, ty) = expected[index];
+            if outputs[index].is_some() {
+                return Err(FormulaError {
+                    column,
+                    message: format!("{name} is assigned twice"),
+                });
+            }
+            if ty == Type::Real && expr.ty() == Type::Complex {
+                return Err(FormulaError {
+                    column,
+                    message: format!("{name} must be real, but its expression is complex"),
+                });
+            }
+            outputs[index] = Some(expr);
+        }
+
+        let outputs = outputs
+            .into_iter()
+            .zip(expected)
+            .map(|(expr, (name, _))| {
+                expr.ok_or_else(|| FormulaError {
+                    column: parser.end_column,
+                    message: format!("{name} is not assigned"),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            kind,
+            source: source.to_string(),
+            outputs,
+        })
+    }
+
+    /// Returns the kind of the formula.
+    pub fn kind(&self) -> FormulaKind {
+        self.kind
+    }
+
+    /// Returns the formula as typed by the user.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the formula on the CPU.
+    ///
+    /// # Arguments
+    ///
+    /// - `variables`: The values of the variables, in the order of
+    ///   [`FormulaKind::variables`]. The imaginary part of real variables is
+    ///   ignored.
+    ///
+    /// # Returns
+    ///
+    /// - The values of the outputs, in the order of [`FormulaKind::outputs`].
+    ///   The imaginary part of real outputs is 0.
+    pub fn evaluate(&self, variables: &[Complex64]) -> Vec<Complex64> {
+        let values: Vec<Value> = self
+            .kind
+            .variables()
+            .iter()
+            .zip(variables)
+            .map(|((_, ty), value)| match ty {
+                Type::Real => Value::Real(value.re),
+                Type::Complex => Value::Complex(*value),
+            })
+            .collect();
+        self.outputs
+            .iter()
+            .map(|expr| expr.evaluate(&values).to_complex())
+            .collect()
+    }
+
+    /// Generates the WGSL function computing the formula.
+    ///
+    /// Its name is given by [`FormulaKind::wgsl_function`], it takes the
+    /// variables as parameters and returns the outputs, packed in a
+    /// `vec2<f32>` for flows. It relies on the helpers of `formula.wgsl` and
+    /// `complex.wgsl`.
+    pub fn to_wgsl(&self) -> String {
+        let parameters = self
+            .kind
+            .variables()
+            .iter()
+            .map(|(name, ty)| format!("{name}: {}", ty.wgsl()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let (return_type, value) = match self.kind {
+            FormulaKind::EscapeTime => ("vec2<f32>", self.outputs[0].to_complex_wgsl()),
+            FormulaKind::Flow => (
+                "vec2<f32>",
+                format!(
+                    "vec2<f32>({}, {})",
+                    self.outputs[0].to_wgsl(),
+                    self.outputs[1].to_wgsl()
+                ),
+            ),
+            FormulaKind::Function => ("f32", self.outputs[0].to_wgsl()),
+        };
+
+        format!(
+            "// Generated from: {}\nfn {}({parameters}) -> {return_type} {{\n    return {value};\n}}\n",
+            self.source.replace('\n', " "),
+            self.kind.wgsl_function(),
+        )
+    }
+}
+
+/// Formulas used by the compute shader, one of each kind.
+#[derive(Clone, Debug)]
+pub struct FormulaSet {
+    formulas: [Formula; 3],
+}
+
+impl Default for FormulaSet {
+    fn default() -> Self {
+        Self {
+            formulas: FormulaKind::ALL.map(|kind| {
+                Formula::parse(kind, kind.default_source())
+                    .expect("Default formulas should be valid")
+            }),
+        }
+    }
+}
+
+impl FormulaSet {
+    /// Returns the formula of the given kind.
+    pub fn get(&self, kind: FormulaKind) -> &Formula {
+        &self.formulas[kind as usize]
+    }
+
+    /// Replaces the formula of its kind.
+    pub fn set(&mut self, formula: Formula) {
+        let index = formula.kind() as usize;
+        self.formulas[index] = formula;
+    }
+
+    /// Generates the WGSL functions of all the formulas.
+    pub fn to_wgsl(&self) -> String {
+        self.formulas
+            .iter()
+            .map(Formula::to_wgsl)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Returns the names of the outputs of a kind of formula, for errors.
+fn output_names(kind: FormulaKind) -> String {
+    kind.outputs()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(" and ")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Number(f64),
+    Identifier(&'a str),
+    Symbol(char),
+}
+
+/// Splits a formula into tokens, each with its column.
+fn tokenize(source: &str) -> Result<Vec<(Token<'_>, usize)>, FormulaError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().enumerate().peekable();
+    while let Some((column, (start, c))) = chars.next() {
+        let column = column + 1;
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        let mut take_while = |predicate: fn(char) -> bool| {
+            while let Some((_, (index, c))) = chars.peek() {
+                if !predicate(*c) {
+                    break;
+                }
+                end = index + c.len_utf8();
+                chars.next();
+            }
+        };
+
+        let token = if c.is_ascii_digit() || c == '.' {
+            take_while(|c| c.is_ascii_digit() || c == '.');
+            // Exponent of scientific notation, as in 1e-3
+            let mut number = &source[start..end];
+            let rest = &source[end..];
+            if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+                let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                let len = digits.chars().take_while(char::is_ascii_digit).count();
+                if len > 0 {
+                    let exponent_len = rest.len() - digits.len() + len;
+                    number = &source[start..end + exponent_len];
+                    for _ in 0..exponent_len {
+                        chars.next();
+                    }
+                }
+            }
+
+            match number.parse::<f64>() {
+                Ok(value) if (value as f32).is_finite() => Token::Number(value),
+                _ => {
+                    return Err(FormulaError {
+                        column,
+                        message: format!("invalid number {number}"),
+                    });
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            take_while(|c| c.is_alphanumeric() || c == '_');
+            Token::Identifier(&source[start..end])
+        } else if "+-*/^(),=;".contains(c) {
+            Token::Symbol(c)
+        } else {
+            return Err(FormulaError {
+                column,
+                message: format!("unexpected character '{c}'"),
+            });
+        };
+        tokens.push((token, column));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Abs,
+    Arg,
+    Re,
+    Im,
+    Conj,
+}
+
+impl Function {
+    const ALL: [Function; 14] = [
+        Function::Sin,
+        Function::Cos,
+        Function::Tan,
+        Function::Sinh,
+        Function::Cosh,
+        Function::Tanh,
+        Function::Exp,
+        Function::Log,
+        Function::Sqrt,
+        Function::Abs,
+        Function::Arg,
+        Function::Re,
+        Function::Im,
+        Function::Conj,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Exp => "exp",
+            Function::Log => "log",
+            Function::Sqrt => "sqrt",
+            Function::Abs => "abs",
+            Function::Arg => "arg",
+            Function::Re => "re",
+            Function::Im => "im",
+            Function::Conj => "conj",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Function::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Returns the type of the result for an argument of type `ty`.
+    fn result_type(&self, ty: Type) -> Type {
+        match self {
+            Function::Abs | Function::Arg | Function::Re | Function::Im => Type::Real,
+            _ => ty,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Constant {
+    Pi,
+    E,
+    I,
+}
+
+/// Typed expression of a formula.
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    Constant(Constant),
+    /// Index of the variable in [`FormulaKind::variables`] and its type.
+    Variable(usize, &'static str, Type),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+/// Largest integer exponent computed by repeated multiplication.
+const MAX_INTEGER_EXPONENT: f64 = 64.0;
+
+impl Expr {
+    fn ty(&self) -> Type {
+        match self {
+            Expr::Number(_) => Type::Real,
+            Expr::Constant(constant) => match constant {
+                Constant::I => Type::Complex,
+                _ => Type::Real,
+            },
+            Expr::Variable(_, _, ty) => *ty,
+            Expr::Neg(expr) => expr.ty(),
+            Expr::Binary(BinaryOp::Pow, base, exponent) => {
+                if base.ty() == Type::Real && exponent.ty() == Type::Real {
+                    Type::Real
+                } else {
+                    Type::Complex
+                }
+            }
+            Expr::Binary(_, lhs, rhs) => {
+                if lhs.ty() == Type::Complex || rhs.ty() == Type::Complex {
+                    Type::Complex
+                } else {
+                    Type::Real
+                }
+            }
+            Expr::Call(function, arg) => function.result_type(arg.ty()),
+        }
+    }
+
+    /// Returns whether the expression does not depend on the variables.
+    fn is_constant(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Constant(_) => true,
+            Expr::Variable(..) => false,
+            Expr::Neg(expr) | Expr::Call(_, expr) => expr.is_constant(),
+            Expr::Binary(_, lhs, rhs) => lhs.is_constant() && rhs.is_constant(),
+        }
+    }
+
+    /// Returns the exponent if the expression is a small integer constant,
+    /// such as `3` or `1 + 1`, which is raised by repeated multiplication.
+    ///
+    /// Folding the constant on the CPU keeps the integer powers of negative
+    /// values defined on the GPU, where `pow` is not.
+    fn integer_exponent(&self) -> Option<i32> {
+        if self.ty() != Type::Real || !self.is_constant() {
+            return None;
+        }
+        match self.evaluate(&[]) {
+            Value::Real(n) if n.fract() == 0.0 && n.abs() <= MAX_INTEGER_EXPONENT => Some(n as i32),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the expression, with the same semantics as the generated
+    /// WGSL code.
+    fn evaluate(&self, variables: &[Value]) -> Value {
+        match self {
+            Expr::Number(n) => Value::Real(*n),
+            Expr::Constant(Constant::Pi) => Value::Real(std::f64::consts::PI),
+            Expr::Constant(Constant::E) => Value::Real(std::f64::consts::E),
+            Expr::Constant(Constant::I) => Value::Complex(Complex64::i()),
+            Expr::Variable(index, _, _) => variables[*index],
+            Expr::Neg(expr) => match expr.evaluate(variables) {
+                Value::Real(x) => Value::Real(-x),
+                Value::Complex(z) => Value::Complex(-z),
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.evaluate(variables), rhs.evaluate(variables));
+                if let (BinaryOp::Pow, Some(n)) = (op, rhs.integer_exponent()) {
+                    return match a {
+                        Value::Real(x) => Value::Real(x.powi(n)),
+                        Value::Complex(z) => Value::Complex(z.powi(n)),
+                    };
+                }
+                match (a, b) {
+                    (Value::Real(x), Value::Real(y)) => Value::Real(match op {
+                        BinaryOp::Add => x + y,
+                        BinaryOp::Sub => x - y,
+                        BinaryOp::Mul => x * y,
+                        BinaryOp::Div => x / y,
+                        BinaryOp::Pow => x.powf(y),
+                    }),
+                    (a, b) => {
+                        let (z, w) = (a.to_complex(), b.to_complex());
+                        Value::Complex(match op {
+                            BinaryOp::Add => z + w,
+                            BinaryOp::Sub => z - w,
+                            BinaryOp::Mul => z * w,
+                            BinaryOp::Div => z / w,
+                            BinaryOp::Pow if z == Complex64::ZERO => Complex64::ZERO,
+                            BinaryOp::Pow => (w * z.ln()).exp(),
+                        })
+                    }
+                }
+            }
+            Expr::Call(function, arg) => match arg.evaluate(variables) {
+                Value::Real(x) => Value::Real(match function {
+                    Function::Sin => x.sin(),
+                    Function::Cos => x.cos(),
+                    Function::Tan => x.tan(),
+                    Function::Sinh => x.sinh(),
+                    Function::Cosh => x.cosh(),
+                    Function::Tanh => x.tanh(),
+                    Function::Exp => x.exp(),
+                    Function::Log => x.ln(),
+                    Function::Sqrt => x.sqrt(),
+                    Function::Abs => x.abs(),
+                    Function::Arg => 0.0_f64.atan2(x),
+                    Function::Re | Function::Conj => x,
+                    Function::Im => 0.0,
+                }),
+                Value::Complex(z) => match function {
+                    Function::Sin => Value::Complex(z.sin()),
+                    Function::Cos => Value::Complex(z.cos()),
+                    Function::Tan => Value::Complex(z.tan()),
+                    Function::Sinh => Value::Complex(z.sinh()),
+                    Function::Cosh => Value::Complex(z.cosh()),
+                    Function::Tanh => Value::Complex(z.tanh()),
+                    Function::Exp => Value::Complex(z.exp()),
+                    Function::Log => Value::Complex(z.ln()),
+                    Function::Sqrt => Value::Complex(z.sqrt()),
+                    Function::Conj => Value::Complex(z.conj()),
+                    Function::Abs => Value::Real(z.norm()),
+                    Function::Arg => Value::Real(z.arg()),
+                    Function::Re => Value::Real(z.re),
+                    Function::Im => Value::Real(z.im),
+                },
+            },
+        }
+    }
+
+    /// Generates the WGSL code of the expression, of type `self.ty()`.
+    fn to_wgsl(&self) -> String {
+        match self {
+            Expr::Number(n) => format!("{:?}", *n as f32),
+            Expr::Constant(Constant::Pi) => format!("{:?}", std::f32::consts::PI),
+            Expr::Constant(Constant::E) => format!("{:?}", std::f32::consts::E),
+            Expr::Constant(Constant::I) => "vec2<f32>(0.0, 1.0)".to_string(),
+            Expr::Variable(_, name, _) => name.to_string(),
+            Expr::Neg(expr) => format!("(-{})", expr.to_wgsl()),
+            Expr::Binary(op, lhs, rhs) => {
+                if let (BinaryOp::Pow, Some(n)) = (op, rhs.integer_exponent()) {
+                    return match lhs.ty() {
+                        Type::Real => format!("rx_powi({}, {n})", lhs.to_wgsl()),
+                        Type::Complex => format!("cx_powi({}, {n})", lhs.to_wgsl()),
+                    };
+                }
+                if self.ty() == Type::Real {
+                    let (a, b) = (lhs.to_wgsl(), rhs.to_wgsl());
+                    return match op {
+                        BinaryOp::Add => format!("({a} + {b})"),
+                        BinaryOp::Sub => format!("({a} - {b})"),
+                        BinaryOp::Mul => format!("({a} * {b})"),
+                        BinaryOp::Div => format!("({a} / {b})"),
+                        BinaryOp::Pow => format!("rx_pow({a}, {b})"),
+                    };
+                }
+
+                let (a, b) = (lhs.to_complex_wgsl(), rhs.to_complex_wgsl());
+                match op {
+                    BinaryOp::Add => format!("({a} + {b})"),
+                    BinaryOp::Sub => format!("({a} - {b})"),
+                    // Scaling by a real value does not need a complex product
+                    BinaryOp::Mul if lhs.ty() == Type::Real => format!("({} * {b})", lhs.to_wgsl()),
+                    BinaryOp::Mul if rhs.ty() == Type::Real => format!("({a} * {})", rhs.to_wgsl()),
+                    BinaryOp::Mul => format!("cx_mul({a}, {b})"),
+                    BinaryOp::Div if rhs.ty() == Type::Real => format!("({a} / {})", rhs.to_wgsl()),
+                    BinaryOp::Div => format!("cx_div({a}, {b})"),
+                    BinaryOp::Pow => format!("cx_pow({a}, {b})"),
+                }
+            }
+            Expr::Call(function, arg) => {
+                let a = arg.to_wgsl();
+                match (arg.ty(), function) {
+                    (Type::Real, Function::Arg) => format!("atan2(0.0, {a})"),
+                    (Type::Real, Function::Re | Function::Conj) => a,
+                    (Type::Real, Function::Im) => "0.0".to_string(),
+                    (Type::Real, function) => format!("{}({a})", function.name()),
+                    (Type::Complex, Function::Abs) => format!("length({a})"),
+                    (Type::Complex, Function::Arg) => format!("cx_arg({a})"),
+                    (Type::Complex, Function::Re) => format!("({a}).x"),
+                    (Type::Complex, Function::Im) => format!("({a}).y"),
+                    (Type::Complex, function) => format!("cx_{}({a})", function.name()),
+                }
+            }
+        }
+    }
+
+    /// Generates the WGSL code of the expression, promoted to a complex value.
+    fn to_complex_wgsl(&self) -> String {
+        match self.ty() {
+            Type::Real => format!("vec2<f32>({}, 0.0)", self.to_wgsl()),
+            Type::Complex => self.to_wgsl(),
+        }
+    }
+}
+
+/// Value of an expression evaluated on the CPU.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+    Real(f64),
+    Complex(Complex64),
+}
+
+impl Value {
+    fn to_complex(self) -> Complex64 {
+        match self {
+            Value::Real(x) => Complex64::new(x, 0.0),
+            Value::Complex(z) => z,
+        }
+    }
+}
+
+/// Recursive descent parser of formulas.
+///
+/// The grammar, from the lowest to the highest precedence, is:
+///
+/// ```text
+/// assignments := assignment (';' assignment)* ';'?
+/// assignment  := (identifier '=')? sum
+/// sum         := product (('+' | '-') product)*
+/// product     := unary (('*' | '/') unary | power)*
+/// unary       := '-' unary | power
+/// power       := primary ('^' unary)?
+/// primary     := number | identifier | identifier '(' sum ')' | '(' sum ')'
+/// ```
+///
+/// A `power` directly following another factor is an implicit
+/// multiplication.
+struct Parser<'a> {
+    kind: FormulaKind,
+    tokens: &'a [(Token<'a>, usize)],
+    position: usize,
+    /// Column just after the end of the formula, for errors at its end.
+    end_column: usize,
+}
+
+/// Assignment with its target, the column of its start and its expression.
+type Assignment<'a> = (Option<&'a str>, usize, Expr);
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).map(|(token, _)| *token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end_column, |(_, column)| *column)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, FormulaError> {
+        Err(FormulaError {
+            column: self.column(),
+            message: message.into(),
+        })
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), FormulaError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{symbol}'"))
+        }
+    }
+
+    fn parse_assignments(&mut self) -> Result<Vec<Assignment<'a>>, FormulaError> {
+        let mut assignments = Vec::new();
+        while self.peek().is_some() {
+            let column = self.column();
+            let target = match (self.peek(), self.tokens.get(self.position + 1)) {
+                (Some(Token::Identifier(name)), Some((Token::Symbol('='), _))) => {
+                    self.position += 2;
+                    Some(name)
+                }
+                _ => None,
+            };
+            let expr = self.parse_sum()?;
+            assignments.push((target, column, expr));
+
+            if !self.eat(';') && self.peek().is_some() {
+                return self.error("expected an operator or ';'");
+            }
+        }
+
+        if assignments.is_empty() {
+            return self.error("the formula is empty");
+        }
+        Ok(assignments)
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.parse_product()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            let rhs = self.parse_product()?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let (op, rhs) = if self.eat('*') {
+                (BinaryOp::Mul, self.parse_unary()?)
+            } else if self.eat('/') {
+                (BinaryOp::Div, self.parse_unary()?)
+            } else if matches!(
+                self.peek(),
+                Some(Token::Number(_) | Token::Identifier(_) | Token::Symbol('('))
+            ) && !self.is_assignment()
+            {
+                (BinaryOp::Mul, self.parse_power()?)
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    /// Returns whether the next tokens start a new assignment, which is
+    /// missing its ';'.
+    fn is_assignment(&self) -> bool {
+        matches!(
+            (self.peek(), self.tokens.get(self.position + 1)),
+            (Some(Token::Identifier(_)), Some((Token::Symbol('='), _)))
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FormulaError> {
+        if self.eat('-') {
+            Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_power()
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, FormulaError> {
+        let base = self.parse_primary()?;
+        if self.eat('^') {
+            let exponent = self.parse_unary()?;
+            Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, FormulaError> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                self.position += 1;
+                Ok(Expr::Number(n))
+            }
+            Some(Token::Symbol('(')) => {
+                self.position += 1;
+                let expr = self.parse_sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Identifier(name)) => {
+                if let Some(function) = Function::from_name(name) {
+                    self.position += 1;
+                    self.expect('(')?;
+                    let arg = self.parse_sum()?;
+                    self.expect(')')?;
+                    return Ok(Expr::Call(function, Box::new(arg)));
+                }
+
+                let constant = match name {
+                    "pi" => Some(Constant::Pi),
+                    "e" => Some(Constant::E),
+                    "i" => Some(Constant::I),
+                    _ => None,
+                };
+                let variables = self.kind.variables();
+                let expr = match (constant, variables.iter().position(|(v, _)| *v == name)) {
+                    (_, Some(index)) => {
+                        let (name, ty) = variables[index];
+                        Expr::Variable(index, name, ty)
+                    }
+                    (Some(constant), None) => Expr::Constant(constant),
+                    (None, None) => {
+                        let names = variables
+                            .iter()
+                            .map(|(name, _)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        return self.error(format!(
+                            "unknown variable or function {name}, the variables are {names}"
+                        ));
+                    }
+                };
+                self.position += 1;
+                Ok(expr)
+            }
+            Some(Token::Symbol(symbol)) => self.error(format!("unexpected '{symbol}'")),
+            None => self.error("unexpected end of the formula"),
+        }
+    }
+}
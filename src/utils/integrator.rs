@@ -0,0 +1,231 @@
+/// Tolerance of the adaptive integrator, relative to the magnitude of the
+/// integrated point, used until it is changed in the UI.
+pub const INITIAL_TOLERANCE: f32 = 1e-4;
+
+/// Smallest and largest factors applied to the step size of the adaptive
+/// integrator between two steps.
+const STEP_FACTOR_RANGE: (f64, f64) = (0.2, 5.0);
+/// Number of times the adaptive integrator shrinks a rejected step before
+/// accepting it anyway, so that stiff regions cannot stall the shader.
+pub const MAX_REJECTED_STEPS: u32 = 8;
+
+/// Integrators of the ODEs of the flow kernels.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`Integrator::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Integrator {
+    /// Forward Euler method, of order 1.
+    Euler = 0,
+    /// Explicit midpoint method, of order 2.
+    Midpoint = 1,
+    /// Classical Runge-Kutta method, of order 4.
+    Rk4 = 2,
+    /// Dormand-Prince method, of order 5, whose step size adapts to keep the
+    /// error estimated by its embedded order 4 solution below the tolerance.
+    DormandPrince = 3,
+    /// Leapfrog method, of order 2, which treats x as a position and y as a
+    /// momentum. It is symplectic for the separable systems x' = f(y),
+    /// y' = g(x), whose energy it conserves over long times.
+    Leapfrog = 4,
+}
+
+impl Integrator {
+    /// All the integrators, in the order they are displayed in the UI.
+    pub const ALL: [Integrator; 5] = [
+        Integrator::Euler,
+        Integrator::Midpoint,
+        Integrator::Rk4,
+        Integrator::DormandPrince,
+        Integrator::Leapfrog,
+    ];
+
+    /// Returns the name of the integrator as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Integrator::Euler => "Euler",
+            Integrator::Midpoint => "Midpoint",
+            Integrator::Rk4 => "RK4",
+            Integrator::DormandPrince => "Dormand-Prince (adaptive)",
+            Integrator::Leapfrog => "Leapfrog (symplectic)",
+        }
+    }
+
+    /// Returns the name of the constant identifying the integrator in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            Integrator::Euler => "INTEGRATOR_EULER",
+            Integrator::Midpoint => "INTEGRATOR_MIDPOINT",
+            Integrator::Rk4 => "INTEGRATOR_RK4",
+            Integrator::DormandPrince => "INTEGRATOR_DORMAND_PRINCE",
+            Integrator::Leapfrog => "INTEGRATOR_LEAPFROG",
+        }
+    }
+
+    /// Returns the order of the integrator, by which its global error
+    /// shrinks as the step size does.
+    pub fn order(&self) -> u32 {
+        match self {
+            Integrator::Euler => 1,
+            Integrator::Midpoint | Integrator::Leapfrog => 2,
+            Integrator::Rk4 => 4,
+            Integrator::DormandPrince => 5,
+        }
+    }
+
+    /// Returns whether the integrator adapts its step size to the tolerance.
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Integrator::DormandPrince)
+    }
+
+    /// Advances z by one step of size dt along the field.
+    ///
+    /// This mirrors `integrate` in the compute shader, without the step size
+    /// control of the adaptive integrator, which takes its order 5 step.
+    /// It computes in f64 whatever the precision of the shaders, so that the
+    /// convergence of the integrators can be tested down to small errors.
+    pub fn step(&self, field: impl Fn([f64; 2]) -> [f64; 2], z: [f64; 2], dt: f64) -> [f64; 2] {
+        match self {
+            Integrator::Euler => axpy(dt, field(z), z),
+            Integrator::Midpoint => {
+                let k1 = field(z);
+                let k2 = field(axpy(0.5 * dt, k1, z));
+                axpy(dt, k2, z)
+            }
+            Integrator::Rk4 => {
+                let k1 = field(z);
+                let k2 = field(axpy(0.5 * dt, k1, z));
+                let k3 = field(axpy(0.5 * dt, k2, z));
+                let k4 = field(axpy(dt, k3, z));
+                let mut next = z;
+                for (k, weight) in [(k1, 1.0), (k2, 2.0), (k3, 2.0), (k4, 1.0)] {
+                    next = axpy(dt * weight / 6.0, k, next);
+                }
+                next
+            }
+            Integrator::DormandPrince => dormand_prince(field, z, dt).0,
+            Integrator::Leapfrog => {
+                // Kick the momentum by half a step, drift the position by a
+                // whole step, then kick the momentum again
+                let y = z[1] + 0.5 * dt * field(z)[1];
+                let x = z[0] + dt * field([z[0], y])[0];
+                [x, y + 0.5 * dt * field([x, y])[1]]
+            }
+        }
+    }
+
+    /// Advances z by one step along the field, whose size is adapted to the
+    /// tolerance for the adaptive integrator.
+    ///
+    /// This mirrors `integrate` in the compute shader. A step of the adaptive
+    /// integrator is rejected and retried with a smaller size while its
+    /// estimated error, relative to `tolerance * (1 + |z|)`, exceeds 1.
+    ///
+    /// # Returns
+    ///
+    /// - The new point, the size of the step taken, and the size proposed for
+    ///   the next step, which are both `dt` for the fixed-step integrators.
+    pub fn adaptive_step(
+        &self,
+        field: impl Fn([f64; 2]) -> [f64; 2],
+        z: [f64; 2],
+        dt: f64,
+        tolerance: f64,
+    ) -> ([f64; 2], f64, f64) {
+        if !self.is_adaptive() {
+            return (self.step(field, z, dt), dt, dt);
+        }
+
+        let scale = tolerance * (1.0 + z[0].hypot(z[1]));
+        let mut dt = dt;
+        let mut rejected = 0;
+        loop {
+            let (next, error) = dormand_prince(&field, z, dt);
+            let error = error[0].hypot(error[1]) / scale;
+            // The error of the order 4 solution scales as dt^5
+            let factor =
+                (0.9 * error.max(1e-10).powf(-0.2)).clamp(STEP_FACTOR_RANGE.0, STEP_FACTOR_RANGE.1);
+            if error <= 1.0 || rejected == MAX_REJECTED_STEPS {
+                return (next, dt, dt * factor);
+            }
+            dt *= factor;
+            rejected += 1;
+        }
+    }
+}
+
+/// Returns a x + y.
+fn axpy(a: f64, x: [f64; 2], y: [f64; 2]) -> [f64; 2] {
+    [a * x[0] + y[0], a * x[1] + y[1]]
+}
+
+/// Takes a step of the Dormand-Prince method.
+///
+/// # Returns
+///
+/// - The order 5 solution, and its difference with the embedded order 4
+///   solution, which estimates the error of the latter.
+fn dormand_prince(
+    field: impl Fn([f64; 2]) -> [f64; 2],
+    z: [f64; 2],
+    dt: f64,
+) -> ([f64; 2], [f64; 2]) {
+    // Butcher tableau, whose last row gives the order 5 solution
+    const A: [&[f64]; 6] = [
+        &[1.0 / 5.0],
+        &[3.0 / 40.0, 9.0 / 40.0],
+        &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+        &[
+            19372.0 / 6561.0,
+            -25360.0 / 2187.0,
+            64448.0 / 6561.0,
+            -212.0 / 729.0,
+        ],
+        &[
+            9017.0 / 3168.0,
+            -355.0 / 33.0,
+            46732.0 / 5247.0,
+            49.0 / 176.0,
+            -5103.0 / 18656.0,
+        ],
+        &[
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+        ],
+    ];
+    // Difference between the weights of the order 5 and order 4 solutions
+    const E: [f64; 7] = [
+        71.0 / 57600.0,
+        0.0,
+        -71.0 / 16695.0,
+        71.0 / 1920.0,
+        -17253.0 / 339200.0,
+        22.0 / 525.0,
+        -1.0 / 40.0,
+    ];
+
+    let mut k = [[0.0; 2]; 7];
+    k[0] = field(z);
+    let mut next = z;
+    for (stage, row) in A.iter().enumerate() {
+        next = z;
+        for (weight, k) in row.iter().zip(&k) {
+            next = axpy(dt * weight, *k, next);
+        }
+        k[stage + 1] = field(next);
+    }
+
+    let mut error = [0.0; 2];
+    for (weight, k) in E.iter().zip(&k) {
+        error = axpy(dt * weight, *k, error);
+    }
+    (next, error)
+}
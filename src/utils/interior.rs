@@ -0,0 +1,183 @@
+use crate::ShaderFloat;
+
+/// Tolerance of the periodicity check, relative to the size of a pixel.
+///
+/// An orbit is considered periodic once it comes back this close to a point
+/// it already visited, along a cycle which attracts nearby orbits.
+pub const PERIODICITY_TOLERANCE: f32 = 1e-5;
+
+/// Checks which stop the iteration of points inside the Mandelbrot and Julia
+/// sets before reaching the maximum number of iterations.
+///
+/// The discriminant of each variant is a bit of the mask of enabled checks
+/// passed to the compute shader, where it is compared to the constant named
+/// by [`InteriorCheck::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteriorCheck {
+    /// Tests whether c is in the main cardioid of the Mandelbrot set.
+    Cardioid = 1,
+    /// Tests whether c is in the period-2 bulb of the Mandelbrot set.
+    Bulb = 2,
+    /// Detects orbits which come back to a previous point along an
+    /// attracting cycle, using Brent's cycle detection algorithm.
+    Periodicity = 4,
+}
+
+impl InteriorCheck {
+    /// All the interior checks, in the order they are displayed in the UI
+    /// and counted by the compute shader.
+    pub const ALL: [InteriorCheck; 3] = [
+        InteriorCheck::Cardioid,
+        InteriorCheck::Bulb,
+        InteriorCheck::Periodicity,
+    ];
+
+    /// Mask enabling all the interior checks.
+    pub const ALL_MASK: u32 = {
+        let mut mask = 0;
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            mask |= Self::ALL[i] as u32;
+            i += 1;
+        }
+        mask
+    };
+
+    /// Returns the name of the check as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            InteriorCheck::Cardioid => "Main cardioid",
+            InteriorCheck::Bulb => "Period-2 bulb",
+            InteriorCheck::Periodicity => "Periodicity",
+        }
+    }
+
+    /// Returns the name of the constant identifying the check in the shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            InteriorCheck::Cardioid => "INTERIOR_CARDIOID",
+            InteriorCheck::Bulb => "INTERIOR_BULB",
+            InteriorCheck::Periodicity => "INTERIOR_PERIODICITY",
+        }
+    }
+
+    /// Returns the index of the check in [`InteriorCheck::ALL`].
+    pub fn index(&self) -> usize {
+        (*self as u32).trailing_zeros() as usize
+    }
+
+    /// Returns whether the check is enabled in a mask of checks.
+    pub fn is_enabled(&self, mask: u32) -> bool {
+        mask & *self as u32 != 0
+    }
+}
+
+/// Returns whether c is in the main cardioid or the period-2 bulb of the
+/// Mandelbrot set, following the enabled checks.
+///
+/// This mirrors `interior_check` in the compute shader.
+pub fn interior_check(c: [ShaderFloat; 2], checks: u32) -> Option<InteriorCheck> {
+    let [x, y] = c;
+    let y2 = y * y;
+
+    if InteriorCheck::Cardioid.is_enabled(checks) {
+        let x = x - 0.25;
+        let q = x * x + y2;
+        if q * (q + x) <= 0.25 * y2 {
+            return Some(InteriorCheck::Cardioid);
+        }
+    }
+
+    if InteriorCheck::Bulb.is_enabled(checks) {
+        let x = x + 1.0;
+        if x * x + y2 <= 0.0625 {
+            return Some(InteriorCheck::Bulb);
+        }
+    }
+
+    None
+}
+
+/// Iterates z -> z^2 + c from z = 0 in the precision of the shaders.
+///
+/// This mirrors `mandelbrot` in the compute shader, to test that the interior
+/// checks do not change the classification of any point.
+///
+/// # Arguments
+///
+/// - `c`: The point of the Mandelbrot set to iterate.
+/// - `max_iter`: The maximum number of iterations.
+/// - `bailout`: The bailout radius.
+/// - `pixel_size`: The size of a pixel, which scales the tolerance of the
+///   periodicity check.
+/// - `checks`: The mask of enabled interior checks.
+///
+/// # Returns
+///
+/// - The number of iterations before escaping, which is `max_iter` for
+///   points inside the set, and the check which stopped the iteration early,
+///   if any.
+pub fn mandelbrot_iterations(
+    c: [ShaderFloat; 2],
+    max_iter: u32,
+    bailout: f32,
+    pixel_size: ShaderFloat,
+    checks: u32,
+) -> (u32, Option<InteriorCheck>) {
+    if let Some(check) = interior_check(c, checks) {
+        return (max_iter, Some(check));
+    }
+
+    let bailout = bailout as ShaderFloat;
+    let tolerance = PERIODICITY_TOLERANCE as ShaderFloat * pixel_size;
+    let check_periodicity = InteriorCheck::Periodicity.is_enabled(checks);
+
+    let mut z: [ShaderFloat; 2] = [0.0, 0.0];
+    let mut z_saved = z;
+    // Derivative of z with respect to the saved point, whose magnitude is
+    // that of the multiplier of the cycle once the orbit comes back
+    let mut dz: [ShaderFloat; 2] = [1.0, 0.0];
+    let mut period = 0;
+    let mut period_limit = 1;
+    let mut iter = 0;
+    while iter < max_iter {
+        let z2 = [z[0] * z[0], z[1] * z[1]];
+        if z2[0] + z2[1] > bailout * bailout {
+            break;
+        }
+
+        if check_periodicity {
+            dz = [
+                2.0 * (z[0] * dz[0] - z[1] * dz[1]),
+                2.0 * (z[0] * dz[1] + z[1] * dz[0]),
+            ];
+        }
+        z = [z2[0] - z2[1] + c[0], 2.0 * z[0] * z[1] + c[1]];
+        iter += 1;
+
+        // Brent's algorithm compares z to a point saved at increasing powers
+        // of two, which finds cycles of any length. Points on the boundary
+        // can land exactly on a repelling cycle, which rounding errors then
+        // make escape, so only attracting cycles stop the iteration.
+        if check_periodicity {
+            let d = [z[0] - z_saved[0], z[1] - z_saved[1]];
+            if d[0] * d[0] + d[1] * d[1] < tolerance * tolerance
+                && dz[0] * dz[0] + dz[1] * dz[1] < 1.0
+            {
+                return (max_iter, Some(InteriorCheck::Periodicity));
+            }
+            period += 1;
+            if period == period_limit {
+                z_saved = z;
+                dz = [1.0, 0.0];
+                period = 0;
+                period_limit *= 2;
+            }
+        }
+    }
+
+    (iter, None)
+}
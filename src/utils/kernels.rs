@@ -0,0 +1,307 @@
+use std::ops::RangeInclusive;
+
+use super::formula::FormulaKind;
+use crate::{FloatChoice, INITIAL_X_RANGE, INITIAL_Y_RANGE};
+
+/// Kernels available in the compute shader.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`Kernel::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kernel {
+    Mandelbrot = 0,
+    Julia = 1,
+    DeepMandelbrot = 2,
+    Multibrot = 3,
+    BurningShip = 4,
+    Tricorn = 5,
+    MagnetI = 6,
+    MagnetII = 7,
+    Phoenix = 8,
+    Sine = 9,
+    Exp = 10,
+    Formula = 11,
+    Newton = 12,
+    MathFn = 13,
+    VanDerPol = 14,
+    FlowFormula = 15,
+    Duffing = 16,
+    Pendulum = 17,
+    MagneticPendulum = 18,
+    LotkaVolterra = 19,
+    FitzHughNagumo = 20,
+    ForcedVanDerPol = 21,
+    ForcedDuffing = 22,
+    Lyapunov = 23,
+    Buddhabrot = 24,
+    Clifford = 25,
+    DeJong = 26,
+    Svensson = 27,
+    Lorenz = 28,
+}
+
+impl Kernel {
+    /// All the kernels, in the order they are displayed in the UI.
+    pub const ALL: [Kernel; 29] = [
+        Kernel::Mandelbrot,
+        Kernel::Julia,
+        Kernel::DeepMandelbrot,
+        Kernel::Multibrot,
+        Kernel::BurningShip,
+        Kernel::Tricorn,
+        Kernel::MagnetI,
+        Kernel::MagnetII,
+        Kernel::Phoenix,
+        Kernel::Sine,
+        Kernel::Exp,
+        Kernel::Formula,
+        Kernel::Newton,
+        Kernel::MathFn,
+        Kernel::VanDerPol,
+        Kernel::FlowFormula,
+        Kernel::Duffing,
+        Kernel::Pendulum,
+        Kernel::MagneticPendulum,
+        Kernel::LotkaVolterra,
+        Kernel::FitzHughNagumo,
+        Kernel::ForcedVanDerPol,
+        Kernel::ForcedDuffing,
+        Kernel::Lyapunov,
+        Kernel::Buddhabrot,
+        Kernel::Clifford,
+        Kernel::DeJong,
+        Kernel::Svensson,
+        Kernel::Lorenz,
+    ];
+
+    /// Returns the name of the kernel as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kernel::Mandelbrot => "Mandelbrot",
+            Kernel::Julia => "Julia",
+            Kernel::DeepMandelbrot => "Mandelbrot (perturbation)",
+            Kernel::Multibrot => "Multibrot",
+            Kernel::BurningShip => "Burning Ship",
+            Kernel::Tricorn => "Tricorn",
+            Kernel::MagnetI => "Magnet I",
+            Kernel::MagnetII => "Magnet II",
+            Kernel::Phoenix => "Phoenix",
+            Kernel::Sine => "Sine",
+            Kernel::Exp => "Exponential",
+            Kernel::Formula => "Formula",
+            Kernel::Newton => "Newton",
+            Kernel::MathFn => "Math function",
+            Kernel::VanDerPol => "Van der Pol",
+            Kernel::FlowFormula => "Flow formula",
+            Kernel::Duffing => "Duffing",
+            Kernel::Pendulum => "Damped pendulum",
+            Kernel::MagneticPendulum => "Magnetic pendulum",
+            Kernel::LotkaVolterra => "Lotka-Volterra",
+            Kernel::FitzHughNagumo => "FitzHugh-Nagumo",
+            Kernel::ForcedVanDerPol => "Forced Van der Pol",
+            Kernel::ForcedDuffing => "Forced Duffing",
+            Kernel::Lyapunov => "Lyapunov",
+            Kernel::Buddhabrot => "Buddhabrot",
+            Kernel::Clifford => "Clifford attractor",
+            Kernel::DeJong => "De Jong attractor",
+            Kernel::Svensson => "Svensson attractor",
+            Kernel::Lorenz => "Lorenz attractor",
+        }
+    }
+
+    /// Returns the name of the constant identifying the kernel in the shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            Kernel::Mandelbrot => "KERNEL_MANDELBROT",
+            Kernel::Julia => "KERNEL_JULIA",
+            Kernel::DeepMandelbrot => "KERNEL_DEEP_MANDELBROT",
+            Kernel::Multibrot => "KERNEL_MULTIBROT",
+            Kernel::BurningShip => "KERNEL_BURNING_SHIP",
+            Kernel::Tricorn => "KERNEL_TRICORN",
+            Kernel::MagnetI => "KERNEL_MAGNET_I",
+            Kernel::MagnetII => "KERNEL_MAGNET_II",
+            Kernel::Phoenix => "KERNEL_PHOENIX",
+            Kernel::Sine => "KERNEL_SINE",
+            Kernel::Exp => "KERNEL_EXP",
+            Kernel::Formula => "KERNEL_FORMULA",
+            Kernel::Newton => "KERNEL_NEWTON",
+            Kernel::MathFn => "KERNEL_MATH_FN",
+            Kernel::VanDerPol => "KERNEL_VAN_DER_POL",
+            Kernel::FlowFormula => "KERNEL_FLOW_FORMULA",
+            Kernel::Duffing => "KERNEL_DUFFING",
+            Kernel::Pendulum => "KERNEL_PENDULUM",
+            Kernel::MagneticPendulum => "KERNEL_MAGNETIC_PENDULUM",
+            Kernel::LotkaVolterra => "KERNEL_LOTKA_VOLTERRA",
+            Kernel::FitzHughNagumo => "KERNEL_FITZHUGH_NAGUMO",
+            Kernel::ForcedVanDerPol => "KERNEL_FORCED_VAN_DER_POL",
+            Kernel::ForcedDuffing => "KERNEL_FORCED_DUFFING",
+            Kernel::Lyapunov => "KERNEL_LYAPUNOV",
+            Kernel::Buddhabrot => "KERNEL_BUDDHABROT",
+            Kernel::Clifford => "KERNEL_CLIFFORD",
+            Kernel::DeJong => "KERNEL_DE_JONG",
+            Kernel::Svensson => "KERNEL_SVENSSON",
+            Kernel::Lorenz => "KERNEL_LORENZ",
+        }
+    }
+
+    /// Returns the index of the kernel in [`Kernel::ALL`].
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the initial render ranges in x and y for the kernel.
+    pub fn initial_ranges(&self) -> ([FloatChoice; 2], [FloatChoice; 2]) {
+        match self {
+            Kernel::Mandelbrot => (INITIAL_X_RANGE, INITIAL_Y_RANGE),
+            Kernel::Julia => ([-1.5, 1.5], [-1.5, 1.5]),
+            Kernel::DeepMandelbrot => (INITIAL_X_RANGE, INITIAL_Y_RANGE),
+            Kernel::Multibrot => ([-1.5, 1.5], [-1.5, 1.5]),
+            Kernel::BurningShip => ([-2.2, 1.3], [-2.0, 1.5]),
+            Kernel::Tricorn => ([-2.25, 1.75], [-2.0, 2.0]),
+            Kernel::MagnetI => ([-1.5, 4.0], [-2.75, 2.75]),
+            Kernel::MagnetII => ([-1.5, 3.5], [-2.5, 2.5]),
+            Kernel::Phoenix => ([-2.0, 1.0], [-1.5, 1.5]),
+            Kernel::Sine => ([-4.0, 4.0], [-4.0, 4.0]),
+            Kernel::Exp => ([-3.5, 2.5], [-3.0, 3.0]),
+            Kernel::Formula => ([-2.5, 1.5], [-2.0, 2.0]),
+            Kernel::Newton => ([-2.0, 2.0], [-2.0, 2.0]),
+            Kernel::MathFn => ([-2.0, 2.0], [-2.0, 2.0]),
+            Kernel::VanDerPol => ([-6.0, 6.0], [-6.0, 6.0]),
+            Kernel::FlowFormula => ([-6.0, 6.0], [-6.0, 6.0]),
+            Kernel::Duffing => ([-2.0, 2.0], [-2.0, 2.0]),
+            Kernel::Pendulum => ([-12.6, 12.6], [-6.0, 6.0]),
+            Kernel::MagneticPendulum => ([-2.0, 2.0], [-2.0, 2.0]),
+            Kernel::LotkaVolterra => ([0.0, 1.5], [0.0, 1.5]),
+            Kernel::FitzHughNagumo => ([-3.0, 3.0], [-3.0, 3.0]),
+            Kernel::ForcedVanDerPol => ([-6.0, 6.0], [-6.0, 6.0]),
+            Kernel::ForcedDuffing => ([-2.0, 2.0], [-2.0, 2.0]),
+            Kernel::Lyapunov => ([2.0, 4.0], [2.0, 4.0]),
+            Kernel::Buddhabrot => ([-2.0, 1.0], [-1.5, 1.5]),
+            Kernel::Clifford => ([-3.0, 3.0], [-3.0, 3.0]),
+            Kernel::DeJong => ([-2.5, 2.5], [-2.5, 2.5]),
+            Kernel::Svensson => ([-8.0, 8.0], [-8.0, 8.0]),
+            Kernel::Lorenz => ([-30.0, 30.0], [-5.0, 55.0]),
+        }
+    }
+
+    /// Returns whether the kernel is an escape-time fractal.
+    pub fn is_escape_time(&self) -> bool {
+        !matches!(self, Kernel::Newton | Kernel::MathFn | Kernel::Lyapunov)
+            && !self.is_flow()
+            && !self.accumulates_density()
+    }
+
+    /// Returns whether the kernel integrates a 2D vector field, which is
+    /// rendered following the flow view.
+    pub fn is_flow(&self) -> bool {
+        matches!(
+            self,
+            Kernel::VanDerPol
+                | Kernel::FlowFormula
+                | Kernel::Duffing
+                | Kernel::Pendulum
+                | Kernel::MagneticPendulum
+                | Kernel::LotkaVolterra
+                | Kernel::FitzHughNagumo
+                | Kernel::ForcedVanDerPol
+                | Kernel::ForcedDuffing
+        )
+    }
+
+    /// Returns whether the kernel is an oscillator driven by a periodic
+    /// forcing, whose amplitude and frequency are set apart from its
+    /// parameters.
+    pub fn is_forced(&self) -> bool {
+        matches!(self, Kernel::ForcedVanDerPol | Kernel::ForcedDuffing)
+    }
+
+    /// Returns whether the kernel accumulates random samples into a density,
+    /// which converges over successive computes.
+    pub fn accumulates_density(&self) -> bool {
+        matches!(self, Kernel::Buddhabrot) || self.is_attractor()
+    }
+
+    /// Returns whether the kernel renders the density of a strange attractor.
+    pub fn is_attractor(&self) -> bool {
+        matches!(
+            self,
+            Kernel::Clifford | Kernel::DeJong | Kernel::Svensson | Kernel::Lorenz
+        )
+    }
+
+    /// Returns the names and ranges of the parameters of the attractors and
+    /// the dynamical systems, which is empty for the other kernels.
+    pub fn parameters(&self) -> &'static [(&'static str, RangeInclusive<f32>)] {
+        match self {
+            Kernel::Duffing | Kernel::ForcedDuffing => &[
+                ("delta", 0.0..=2.0),
+                ("alpha", -2.0..=2.0),
+                ("beta", 0.0..=2.0),
+            ],
+            Kernel::Pendulum => &[("gamma", 0.0..=2.0), ("torque", 0.0..=1.0)],
+            Kernel::MagneticPendulum => &[("spring", 0.0..=1.0), ("height", 0.05..=1.0)],
+            Kernel::LotkaVolterra => &[("a", 0.0..=3.0), ("b", 0.0..=3.0)],
+            Kernel::FitzHughNagumo => &[
+                ("a", -1.0..=1.5),
+                ("b", 0.0..=3.0),
+                ("epsilon", 0.01..=1.0),
+                ("current", -1.0..=1.0),
+            ],
+            Kernel::Clifford | Kernel::DeJong | Kernel::Svensson => &[
+                ("a", -3.0..=3.0),
+                ("b", -3.0..=3.0),
+                ("c", -3.0..=3.0),
+                ("d", -7.0..=7.0),
+            ],
+            Kernel::Lorenz => &[
+                ("sigma", 0.0..=20.0),
+                ("rho", 0.0..=50.0),
+                ("beta", 0.0..=5.0),
+            ],
+            _ => &[],
+        }
+    }
+
+    /// Returns the initial parameters of the kernel, following
+    /// [`Kernel::parameters`].
+    pub fn initial_parameters(&self) -> [f32; 4] {
+        match self {
+            Kernel::Duffing => [0.25, -1.0, 1.0, 0.0],
+            Kernel::ForcedDuffing => [0.3, -1.0, 1.0, 0.0],
+            Kernel::Pendulum => [0.2, 0.0, 0.0, 0.0],
+            Kernel::MagneticPendulum => [0.2, 0.25, 0.0, 0.0],
+            Kernel::LotkaVolterra => [1.5, 1.5, 0.0, 0.0],
+            Kernel::FitzHughNagumo => [0.7, 0.8, 0.08, 0.0],
+            Kernel::Clifford => [-1.4, 1.6, 1.0, 0.7],
+            Kernel::DeJong => [1.4, -2.3, 2.4, -2.1],
+            Kernel::Svensson => [1.4, 1.56, 1.4, -6.56],
+            Kernel::Lorenz => [10.0, 28.0, 8.0 / 3.0, 0.0],
+            _ => [0.0; 4],
+        }
+    }
+
+    /// Returns the kind of user formula evaluated by the kernel, if any.
+    pub fn formula_kind(&self) -> Option<FormulaKind> {
+        match self {
+            Kernel::Formula => Some(FormulaKind::EscapeTime),
+            Kernel::FlowFormula => Some(FormulaKind::Flow),
+            Kernel::MathFn => Some(FormulaKind::Function),
+            _ => None,
+        }
+    }
+
+    /// Returns the initial bailout radius for the kernel.
+    ///
+    /// For the sine and exponential maps, the bailout applies to the
+    /// imaginary and real parts of z respectively.
+    pub fn initial_bailout(&self) -> f32 {
+        match self {
+            Kernel::MagnetI | Kernel::MagnetII => 100.0,
+            Kernel::Sine | Kernel::Exp => 50.0,
+            _ => 2.0,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::integrator::Integrator;
+
+/// Step size of the integration of the limit cycle, small enough for the
+/// relaxation oscillations of large mu.
+const CYCLE_DT: f64 = 1e-3;
+/// Duration of the integration before the cycle is recorded, per unit of mu,
+/// so that the trajectory settles on the cycle.
+const TRANSIENT_TIME: f64 = 10.0;
+
+/// Limit cycle of the unforced Van der Pol oscillator, sampled at regular
+/// times along one period.
+pub struct LimitCycle {
+    /// Points of the cycle, starting where x is largest, the i-th of `n`
+    /// points being reached at the phase i / n
+    pub points: Vec<[f64; 2]>,
+    /// Duration of one turn of the cycle
+    pub period: f64,
+}
+
+/// Vector field of the Van der Pol oscillator, mirroring `step_vdp` in the
+/// compute shader.
+pub fn van_der_pol_field(mu: f64) -> impl Fn([f64; 2]) -> [f64; 2] {
+    move |[x, y]| [y, mu * (1.0 - x * x) * y - x]
+}
+
+/// Computes the limit cycle of the Van der Pol oscillator with RK4.
+///
+/// The trajectory from (2, 0) first settles on the cycle, then one period is
+/// recorded between two crossings of the section y = 0 with x > 0.
+///
+/// # Arguments
+///
+/// - `mu`: The damping parameter of the oscillator, which must be positive
+///   for the cycle to attract the trajectories.
+/// - `len`: The number of points sampled along the cycle.
+pub fn van_der_pol_cycle(mu: f64, len: usize) -> LimitCycle {
+    let field = van_der_pol_field(mu);
+    let step = |z| Integrator::Rk4.step(&field, z, CYCLE_DT);
+
+    let mut z = [2.0, 0.0];
+    for _ in 0..((TRANSIENT_TIME * (1.0 + mu)) / CYCLE_DT) as usize {
+        z = step(z);
+    }
+
+    // Returns the fraction of the step from z to next at which it crosses
+    // the section downwards, if it does
+    let crossing = |z: [f64; 2], next: [f64; 2]| {
+        (z[0] > 0.0 && z[1] > 0.0 && next[1] <= 0.0).then(|| z[1] / (z[1] - next[1]))
+    };
+
+    // Find the first crossing, from which the cycle starts
+    let (mut samples, start) = loop {
+        let next = step(z);
+        if let Some(fraction) = crossing(z, next) {
+            break (vec![z, next], fraction);
+        }
+        z = next;
+    };
+
+    // Record the trajectory until the next crossing. The time of the i-th
+    // sample is i steps, and the crossings are interpolated within their
+    // steps
+    let period = loop {
+        let last = samples[samples.len() - 1];
+        let next = step(last);
+        samples.push(next);
+        if let Some(fraction) = crossing(last, next) {
+            break (samples.len() as f64 - 2.0 + fraction - start) * CYCLE_DT;
+        }
+    };
+
+    let points = (0..len)
+        .map(|i| {
+            let time = start + i as f64 / len as f64 * period / CYCLE_DT;
+            let index = time.floor() as usize;
+            let fraction = time - time.floor();
+            let (a, b) = (samples[index], samples[index + 1]);
+            [
+                a[0] + fraction * (b[0] - a[0]),
+                a[1] + fraction * (b[1] - a[1]),
+            ]
+        })
+        .collect();
+
+    LimitCycle { points, period }
+}
@@ -0,0 +1,124 @@
+use nannou::image::{RgbaImage, imageops};
+
+/// Shapes of the orbit traps.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`TrapShape::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapShape {
+    /// Measures the distance of the orbit to the center of the trap.
+    Point = 0,
+    /// Measures the distance of the orbit to the line through the center of
+    /// the trap, in the direction of its angle.
+    Line = 1,
+    /// Measures the distance of the orbit to the two perpendicular lines
+    /// through the center of the trap.
+    Cross = 2,
+    /// Samples an image centered on the trap, whose side is twice the radius
+    /// of the trap. The orbit is caught by the first opaque pixel it lands on,
+    /// which colors the pixel whatever the trap value.
+    Image = 3,
+}
+
+impl TrapShape {
+    /// All the trap shapes, in the order they are displayed in the UI.
+    pub const ALL: [TrapShape; 4] = [
+        TrapShape::Point,
+        TrapShape::Line,
+        TrapShape::Cross,
+        TrapShape::Image,
+    ];
+
+    /// Returns the name of the trap shape as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapShape::Point => "Point",
+            TrapShape::Line => "Line",
+            TrapShape::Cross => "Cross",
+            TrapShape::Image => "Image",
+        }
+    }
+
+    /// Returns the name of the constant identifying the trap shape in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            TrapShape::Point => "TRAP_POINT",
+            TrapShape::Line => "TRAP_LINE",
+            TrapShape::Cross => "TRAP_CROSS",
+            TrapShape::Image => "TRAP_IMAGE",
+        }
+    }
+}
+
+/// Values of the orbit which color the pixels with an orbit trap.
+///
+/// The discriminant of each variant is passed to the compute shader, where it
+/// is compared to the constant named by [`TrapValue::shader_constant`].
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapValue {
+    /// The smallest distance from the orbit to the trap.
+    MinDistance = 0,
+    /// The average distance from the orbit to the trap.
+    AverageDistance = 1,
+    /// The first iteration at which the orbit comes within the radius of the
+    /// trap.
+    FirstHit = 2,
+}
+
+impl TrapValue {
+    /// All the trap values, in the order they are displayed in the UI.
+    pub const ALL: [TrapValue; 3] = [
+        TrapValue::MinDistance,
+        TrapValue::AverageDistance,
+        TrapValue::FirstHit,
+    ];
+
+    /// Returns the name of the trap value as displayed in the UI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapValue::MinDistance => "Minimum distance",
+            TrapValue::AverageDistance => "Average distance",
+            TrapValue::FirstHit => "First hit",
+        }
+    }
+
+    /// Returns the name of the constant identifying the trap value in the
+    /// shaders.
+    ///
+    /// The constant is defined by the shader preprocessor.
+    pub fn shader_constant(&self) -> &'static str {
+        match self {
+            TrapValue::MinDistance => "TRAP_MIN_DISTANCE",
+            TrapValue::AverageDistance => "TRAP_AVERAGE_DISTANCE",
+            TrapValue::FirstHit => "TRAP_FIRST_HIT",
+        }
+    }
+}
+
+/// Downscales an image of the image trap so that neither of its sides exceeds
+/// `max_dimension`, the largest texture the GPU supports, keeping its aspect
+/// ratio.
+///
+/// # Returns
+///
+/// - The downscaled image, or `None` if the image already fits.
+pub fn fit_trap_image(image: &RgbaImage, max_dimension: u32) -> Option<RgbaImage> {
+    let (w, h) = image.dimensions();
+    if w <= max_dimension && h <= max_dimension {
+        return None;
+    }
+
+    let scale = max_dimension as f64 / w.max(h) as f64;
+    let size = |side: u32| ((side as f64 * scale).round() as u32).clamp(1, max_dimension);
+    Some(imageops::resize(
+        image,
+        size(w),
+        size(h),
+        imageops::FilterType::Triangle,
+    ))
+}
@@ -0,0 +1,434 @@
+use num::{BigInt, Float, ToPrimitive, Zero};
+
+use super::bla::BlaTable;
+
+/// Minimum size of the view for deep zooms, limited by the range of `f64`.
+pub const MAX_DEEP_ZOOM_DELTA: f64 = 1e-300;
+/// Minimum number of fractional bits used for the center of a deep zoom.
+const MIN_PRECISION: u32 = 64;
+/// Number of fractional bits kept beyond the size of the view.
+const EXTRA_PRECISION: u32 = 64;
+/// Exponent given to zero deltas, far below any exponent of a nonzero delta.
+const ZERO_EXPONENT: i32 = -100_000;
+
+/// Fixed-point number with `precision` fractional bits.
+///
+/// This is used to store the center of deep zooms, whose coordinates need far
+/// more precision than what `f64` provides.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixed {
+    mantissa: BigInt,
+    precision: u32,
+}
+
+impl Fixed {
+    /// Converts a float to a fixed-point number with the given precision.
+    ///
+    /// Bits of the float beyond the precision are truncated.
+    pub fn from_f64(value: f64, precision: u32) -> Self {
+        let (mantissa, exponent, sign) = value.integer_decode();
+        let mantissa = BigInt::from(mantissa) * sign;
+        let shift = exponent as i64 + precision as i64;
+        let mantissa = if shift >= 0 {
+            mantissa << shift as usize
+        } else {
+            mantissa >> (-shift) as usize
+        };
+        Self {
+            mantissa,
+            precision,
+        }
+    }
+
+    /// Converts the fixed-point number to the nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        mantissa_to_f64(&self.mantissa, self.precision)
+    }
+
+    /// Returns the number of fractional bits of the number.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns the number with a different precision.
+    pub fn with_precision(&self, precision: u32) -> Self {
+        let mantissa = if precision >= self.precision {
+            &self.mantissa << (precision - self.precision) as usize
+        } else {
+            &self.mantissa >> (self.precision - precision) as usize
+        };
+        Self {
+            mantissa,
+            precision,
+        }
+    }
+
+    /// Adds a float to the number, keeping its precision.
+    pub fn add_f64(&self, value: f64) -> Self {
+        let other = Self::from_f64(value, self.precision);
+        Self {
+            mantissa: &self.mantissa + other.mantissa,
+            precision: self.precision,
+        }
+    }
+}
+
+/// View used for deep zooms with perturbation theory.
+///
+/// The center of the view is stored with arbitrary precision while its size
+/// is stored as an `f64`, which allows zooming down to about 1e-300.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeepZoomView {
+    center: (Fixed, Fixed),
+    size: (f64, f64),
+}
+
+impl DeepZoomView {
+    /// Creates a new view covering the given x and y ranges.
+    pub fn from_ranges(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        let size = (x_range.1 - x_range.0, y_range.1 - y_range.0);
+        let precision = Self::required_precision(size);
+        let center = (
+            Fixed::from_f64(0.5 * (x_range.0 + x_range.1), precision),
+            Fixed::from_f64(0.5 * (y_range.0 + y_range.1), precision),
+        );
+        Self { center, size }
+    }
+
+    /// Returns the x and y ranges of the view, rounded to `f64`.
+    pub fn get_ranges(&self) -> ((f64, f64), (f64, f64)) {
+        let (cx, cy) = (self.center.0.to_f64(), self.center.1.to_f64());
+        let (hw, hh) = (0.5 * self.size.0, 0.5 * self.size.1);
+        ((cx - hw, cx + hw), (cy - hh, cy + hh))
+    }
+
+    /// Returns the center of the view, rounded to `f64`.
+    pub fn get_center(&self) -> (f64, f64) {
+        (self.center.0.to_f64(), self.center.1.to_f64())
+    }
+
+    /// Returns the size of the view in x and y.
+    pub fn get_size(&self) -> (f64, f64) {
+        self.size
+    }
+
+    /// Returns the size of the view as `f32` mantissas sharing a power of two
+    /// exponent, so that it can be passed to the GPU whatever the zoom depth.
+    pub fn get_scaled_size(&self) -> ([f32; 2], i32) {
+        let exponent = self.size.0.max(self.size.1).log2().ceil() as i32;
+        let scale = 2.0_f64.powi(-exponent);
+        (
+            [(self.size.0 * scale) as f32, (self.size.1 * scale) as f32],
+            exponent,
+        )
+    }
+
+    /// Zooms the view by a factor of `zoom_factor` centered at a relative
+    /// `zoom_focus` point.
+    ///
+    /// # Arguments
+    ///
+    /// - `zoom_factor`: The factor to zoom in by.
+    /// - `zoom_focus`: The relative position of the zoom center in the view.
+    pub fn zoom_relative(&mut self, zoom_factor: f64, zoom_focus: (f64, f64)) {
+        // Move the center towards the focus point so that it stays fixed
+        let offset = (
+            (zoom_focus.0 - 0.5) * self.size.0 * (1.0 - zoom_factor),
+            (zoom_focus.1 - 0.5) * self.size.1 * (1.0 - zoom_factor),
+        );
+        self.size = (self.size.0 * zoom_factor, self.size.1 * zoom_factor);
+
+        let precision = Self::required_precision(self.size);
+        self.center = (
+            self.center.0.with_precision(precision).add_f64(offset.0),
+            self.center.1.with_precision(precision).add_f64(offset.1),
+        );
+    }
+
+    /// Shifts the view by an offset relative to its size.
+    pub fn shift_relative(&mut self, offset: (f64, f64)) {
+        self.center = (
+            self.center.0.add_f64(offset.0 * self.size.0),
+            self.center.1.add_f64(offset.1 * self.size.1),
+        );
+    }
+
+    /// Computes the reference orbit of the Mandelbrot set at the center of the
+    /// view.
+    ///
+    /// The orbit stops after `max_iter` iterations or after the first point
+    /// whose modulus is greater than `bailout`.
+    ///
+    /// # Returns
+    ///
+    /// - The orbit, starting at z = 0, rounded to `f32`.
+    pub fn reference_orbit(&self, max_iter: u32, bailout: f64) -> Vec<[f32; 2]> {
+        let precision = self.center.0.precision as usize;
+        let (cr, ci) = (&self.center.0.mantissa, &self.center.1.mantissa);
+        let bailout2 = bailout * bailout;
+
+        let mut orbit = Vec::with_capacity(max_iter as usize + 1);
+        let mut zr = BigInt::zero();
+        let mut zi = BigInt::zero();
+        orbit.push([0.0, 0.0]);
+
+        for _ in 0..max_iter {
+            // z = z^2 + c
+            let zr2 = (&zr * &zr) >> precision;
+            let zi2 = (&zi * &zi) >> precision;
+            let zri = (&zr * &zi) >> (precision - 1);
+            zr = zr2 - zi2 + cr;
+            zi = zri + ci;
+
+            let z = (
+                mantissa_to_f64(&zr, precision as u32),
+                mantissa_to_f64(&zi, precision as u32),
+            );
+            orbit.push([z.0 as f32, z.1 as f32]);
+
+            if z.0 * z.0 + z.1 * z.1 > bailout2 {
+                break;
+            }
+        }
+
+        orbit
+    }
+
+    /// Returns the number of fractional bits needed for the center of a view
+    /// of the given size.
+    fn required_precision(size: (f64, f64)) -> u32 {
+        let smallest = size.0.abs().min(size.1.abs());
+        let bits = (-smallest.log2()).ceil().max(0.0) as u32;
+        (bits + EXTRA_PRECISION).max(MIN_PRECISION)
+    }
+}
+
+/// Counts the iterations of the Mandelbrot set before the point offset by `dc`
+/// from the reference point escapes, iterating its difference to the
+/// reference orbit like the perturbation kernel does.
+///
+/// This is a CPU reference of the perturbation kernel, limited to offsets
+/// within the range of `f64`.
+///
+/// # Arguments
+///
+/// - `reference_orbit`: The reference orbit, starting at z = 0.
+/// - `dc`: The offset of the point from the reference point.
+/// - `max_iter`: The maximum number of iterations.
+/// - `bailout`: The escape radius.
+/// - `bla`: If given, blocks of iterations are skipped using the table
+///   whenever it is safe to do so.
+pub fn escape_iterations(
+    reference_orbit: &[[f32; 2]],
+    dc: (f64, f64),
+    max_iter: u32,
+    bailout: f64,
+    bla: Option<&BlaTable>,
+) -> u32 {
+    let bailout2 = bailout * bailout;
+    let orbit = |m: usize| (reference_orbit[m][0] as f64, reference_orbit[m][1] as f64);
+
+    let mut dz = (0.0, 0.0);
+    let mut m = 0;
+    let mut iter = 0;
+    while iter < max_iter {
+        // Check for divergence of z = Z + dz
+        let mut z_ref = orbit(m);
+        let z = (z_ref.0 + dz.0, z_ref.1 + dz.1);
+        if z.0 * z.0 + z.1 * z.1 > bailout2 {
+            break;
+        }
+
+        // Rebase onto the start of the reference orbit on glitches
+        if z.0 * z.0 + z.1 * z.1 < dz.0 * dz.0 + dz.1 * dz.1 || m + 1 >= reference_orbit.len() {
+            dz = z;
+            m = 0;
+            z_ref = orbit(0);
+        }
+
+        // Skip as many iterations as the approximation allows
+        let log2_dz = dz.0.hypot(dz.1).log2() as f32;
+        if let Some((step, skip)) = bla.and_then(|bla| bla.lookup(m, log2_dz, max_iter - iter)) {
+            dz = step.apply(dz, dc);
+            m += skip as usize;
+            iter += skip;
+            continue;
+        }
+
+        // dz = 2 Z dz + dz^2 + dc
+        dz = (
+            2.0 * (z_ref.0 * dz.0 - z_ref.1 * dz.1) + dz.0 * dz.0 - dz.1 * dz.1 + dc.0,
+            2.0 * (z_ref.0 * dz.1 + z_ref.1 * dz.0) + 2.0 * dz.0 * dz.1 + dc.1,
+        );
+        m += 1;
+        iter += 1;
+    }
+
+    iter
+}
+
+/// Counts the iterations like [`escape_iterations`], with the delta stored as
+/// an `f32` mantissa w and a power of two exponent k, dz = w 2^k.
+///
+/// This mirrors `mandelbrot_perturbation` in the compute shader, including
+/// its glitch detection and rebasing, so that it also holds for offsets far
+/// below the range of `f32`.
+///
+/// # Arguments
+///
+/// - `reference_orbit`: The reference orbit, starting at z = 0.
+/// - `dc`: The offset of the point from the reference point, as a mantissa
+///   of the power of two `dc_exponent`, like the size given by
+///   [`DeepZoomView::get_scaled_size`].
+/// - `dc_exponent`: The exponent of the offset.
+/// - `max_iter`: The maximum number of iterations.
+/// - `bailout`: The escape radius.
+/// - `bla`: If given, blocks of iterations are skipped using the table
+///   whenever it is safe to do so.
+pub fn escape_iterations_scaled(
+    reference_orbit: &[[f32; 2]],
+    dc: [f32; 2],
+    dc_exponent: i32,
+    max_iter: u32,
+    bailout: f64,
+    bla: Option<&BlaTable>,
+) -> u32 {
+    let bailout2 = (bailout * bailout) as f32;
+    let mul = |a: [f32; 2], b: [f32; 2]| [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]];
+    let add = |a: [f32; 2], b: [f32; 2]| [a[0] + b[0], a[1] + b[1]];
+    let times = |a: [f32; 2], x: f32| [a[0] * x, a[1] * x];
+    let dot = |a: [f32; 2]| a[0] * a[0] + a[1] * a[1];
+
+    let mut w = [0.0; 2];
+    let mut k = dc_exponent;
+    let mut m = 0;
+    let mut iter = 0;
+    while iter < max_iter {
+        // Check for divergence of z = Z + dz
+        let mut z_ref = reference_orbit[m];
+        let z = add(z_ref, scale_exp2(w, k));
+        if dot(z) > bailout2 {
+            break;
+        }
+
+        // Glitch detection, only computed at the scale of w once |Z| < 2 |dz|
+        let log2_dz = log2_length(w) + k as f32;
+        let z_near = log2_length(z_ref) < log2_dz + 1.0;
+        let z_scaled = if z_near {
+            add(scale_exp2(z_ref, -k), w)
+        } else {
+            w
+        };
+        let glitch = z_near && dot(z_scaled) < dot(w);
+
+        // Rebase onto the start of the reference orbit, where Z = 0
+        if glitch || m + 1 >= reference_orbit.len() {
+            if z_near {
+                w = z_scaled;
+            } else {
+                w = z;
+                k = 0;
+            }
+            (w, k) = renormalize(w, k);
+            m = 0;
+            z_ref = reference_orbit[0];
+        }
+
+        // Skip as many iterations as the approximation allows
+        let log2_dz = dot(w).sqrt().max(1e-30).log2() + k as f32;
+        if let Some((step, skip)) = bla.and_then(|bla| bla.lookup(m, log2_dz, max_iter - iter)) {
+            (w, k) = step.apply_scaled((w, k), (dc, dc_exponent));
+            m += skip as usize;
+            iter += skip;
+            continue;
+        }
+
+        // dz = 2 Z dz + dz^2 + dc, with the largest term used as scale as any
+        // of them can dominate when Z is close to 0
+        let zw = times(mul(z_ref, w), 2.0);
+        let ww = [w[0] * w[0] - w[1] * w[1], 2.0 * w[0] * w[1]];
+        let e = (k + vec_exponent(zw))
+            .max(2 * k + vec_exponent(ww))
+            .max(dc_exponent + vec_exponent(dc));
+        w = add(
+            add(scale_exp2(zw, k - e), scale_exp2(ww, 2 * k - e)),
+            scale_exp2(dc, dc_exponent - e),
+        );
+        k = e;
+        m += 1;
+        iter += 1;
+
+        (w, k) = renormalize(w, k);
+    }
+
+    iter
+}
+
+/// Returns 2^e, flushed to 0 below the normal range of `f32` and saturated at
+/// the largest power of two above it.
+///
+/// This mirrors `exp2i` in the compute shader.
+pub fn exp2i(e: i32) -> f32 {
+    if e < -126 {
+        return 0.0;
+    }
+    f32::from_bits(((e.min(127) + 127) as u32) << 23)
+}
+
+/// Returns the exponent e such that x = f * 2^e with 0.5 <= |f| < 1, or 0 if
+/// x is 0 or subnormal.
+///
+/// This mirrors `exponent_of` in the compute shader.
+fn exponent_of(x: f32) -> i32 {
+    let biased = ((x.to_bits() >> 23) & 0xff) as i32;
+    if biased == 0 { 0 } else { biased - 126 }
+}
+
+/// Returns the exponent of the largest component of v, or a very small
+/// exponent if v is 0 or subnormal so that it is never used as scale.
+///
+/// This mirrors `vec_exponent` in the compute shader.
+fn vec_exponent(v: [f32; 2]) -> i32 {
+    let largest = v[0].abs().max(v[1].abs());
+    if largest < f32::MIN_POSITIVE {
+        return ZERO_EXPONENT;
+    }
+    exponent_of(largest)
+}
+
+/// Returns v * 2^e without saturating the power of two, like `scale_exp2` in
+/// the compute shader.
+fn scale_exp2(v: [f32; 2], e: i32) -> [f32; 2] {
+    let half = e / 2;
+    let (a, b) = (exp2i(half), exp2i(e - half));
+    [v[0] * a * b, v[1] * a * b]
+}
+
+/// Returns log2 |v| for a vector of any magnitude, or -1e30 if v is 0 or
+/// subnormal, like `log2_length` in the compute shader.
+fn log2_length(v: [f32; 2]) -> f32 {
+    let largest = v[0].abs().max(v[1].abs());
+    let e = exponent_of(largest);
+    if e == 0 && largest < 0.5 {
+        return -1e30;
+    }
+    let [x, y] = scale_exp2(v, -e);
+    (x * x + y * y).sqrt().log2() + e as f32
+}
+
+/// Scales w so that its largest component is in [0.5, 1), returning it with
+/// the exponent k updated so that w * 2^k is unchanged.
+fn renormalize(w: [f32; 2], k: i32) -> ([f32; 2], i32) {
+    let e = exponent_of(w[0].abs().max(w[1].abs()));
+    (scale_exp2(w, -e), k + e)
+}
+
+/// Converts the mantissa of a fixed-point number with `precision` fractional
+/// bits to the nearest `f64`.
+fn mantissa_to_f64(mantissa: &BigInt, precision: u32) -> f64 {
+    // Only keep the 64 most significant bits so that the conversion does not
+    // overflow for large precisions
+    let excess = mantissa.bits().saturating_sub(64);
+    let truncated = (mantissa >> excess as usize).to_f64().unwrap_or(0.0);
+    truncated * 2.0_f64.powi(excess as i32 - precision as i32)
+}
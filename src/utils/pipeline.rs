@@ -1,31 +1,77 @@
-use std::cell::RefMut;
+use std::{
+    cell::RefMut,
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
 
 use nannou::{
     image::{self, ImageBuffer},
     prelude::*,
 };
 
-use super::pipeline_buffers::{ComputeData, PostProcessingData};
+use super::{
+    bla::BlaStep,
+    coloring::{Coloring, InteriorColoring},
+    flow::{FlowView, LOCKING_TOLERANCE, LicColoring, MAX_LOCKING_PERIOD},
+    formula::FormulaSet,
+    integrator::{Integrator, MAX_REJECTED_STEPS},
+    interior::{InteriorCheck, PERIODICITY_TOLERANCE},
+    kernels::Kernel,
+    orbit_trap::{TrapShape, TrapValue, fit_trap_image},
+    pipeline_buffers::{
+        BUDDHABROT_SEED, ComputeData, LIMIT_CYCLE_LEN, LimitCycleData, LyapunovData,
+        MAX_LYAPUNOV_SEQUENCE_LEN, MAX_NEWTON_ROOTS, MAX_REFERENCE_ORBIT_LEN, NewtonData,
+        OrbitTrapData, PostProcessingData,
+    },
+    preprocessor::{Preprocessor, SourceLine},
+    shader_watcher::rebuild_or_keep,
+};
 
 pub struct GPUPipeline {
     texture: wgpu::Texture,
     texture_view: wgpu::TextureView,
     compute_data_buffer: wgpu::Buffer,
     processing_data_buffer: wgpu::Buffer,
+    newton_data_buffer: wgpu::Buffer,
+    reference_orbit_buffer: wgpu::Buffer,
+    bla_table_buffer: wgpu::Buffer,
+    early_exits_buffer: wgpu::Buffer,
+    early_exits_readback_buffer: wgpu::Buffer,
+    /// Kernel of the early exit counters whose readback buffer is being
+    /// mapped, during which the computes do not copy their counters to it.
+    early_exits_mapping: Option<Kernel>,
+    /// Result of mapping the readback buffer, set by its callback.
+    early_exits_map_result: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    /// Kernel of the last compute requested while the readback buffer was
+    /// mapped, whose counters still have to be read.
+    early_exits_outdated: Option<Kernel>,
+    orbit_trap_data_buffer: wgpu::Buffer,
+    lyapunov_data_buffer: wgpu::Buffer,
+    /// Density of the Buddhabrot or of the attractors, with three channels
+    /// per pixel of the texture.
+    density_buffer: wgpu::Buffer,
+    limit_cycle_data_buffer: wgpu::Buffer,
+    /// Image sampled by the image orbit trap.
+    trap_image: wgpu::Texture,
+    trap_image_view: wgpu::TextureView,
     // Generate texture
     compute_bgl: wgpu::BindGroupLayout,
     compute_bg: wgpu::BindGroup,
-    compute_pipeline: wgpu::ComputePipeline,
+    compute_pipelines: ComputePipelines,
     // Post-processing
-    min_max_pipeline: wgpu::ComputePipeline,
-    recalibrate_pipeline: wgpu::ComputePipeline,
-    histogram_pipeline: wgpu::ComputePipeline,
-    cdf_pipeline: wgpu::ComputePipeline,
-    equalize_pipeline: wgpu::ComputePipeline,
+    post_processing_pipelines: PostProcessingPipelines,
     // Render
     render_bgl: wgpu::BindGroupLayout,
     render_bg: wgpu::BindGroup,
     render_pipeline: wgpu::RenderPipeline,
+    msaa_samples: u32,
+    // Shaders
+    /// Sources of the shader files, indexed by their name.
+    shader_sources: HashMap<String, String>,
+    /// User formulas compiled into the compute shader.
+    formulas: FormulaSet,
     // Settings
     /// Enables or disables post-processing.
     pub enable_post_processing: bool,
@@ -36,12 +82,28 @@ impl GPUPipeline {
     const WORKGROUP_SIZE: u32 = 16;
     /// Format of the texture used for the compute and render pipelines.
     const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+    /// Format of the image sampled by the image orbit trap.
+    const TRAP_IMAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
     /// Number of channels in the texture.
     const NUM_CHANNELS: u32 = 4;
     /// Number of bytes per channel for the texture.
     const BYTES_PER_CHANNEL: u32 = 4;
     /// Number of bytes per pixel for the texture.
     pub const BYTES_PER_PIXEL: u32 = Self::NUM_CHANNELS * Self::BYTES_PER_CHANNEL;
+    /// Shader files compiled into the application, with their source. They
+    /// can be replaced at runtime with [`GPUPipeline::update_shader_sources`]
+    /// and restored with [`GPUPipeline::embedded_shader_sources`].
+    pub const SHADER_FILES: [(&'static str, &'static str); 6] = [
+        ("compute.wgsl", include_str!("shaders/compute.wgsl")),
+        (
+            "post_processing.wgsl",
+            include_str!("shaders/post_processing.wgsl"),
+        ),
+        ("render.wgsl", include_str!("shaders/render.wgsl")),
+        ("common.wgsl", include_str!("shaders/common.wgsl")),
+        ("complex.wgsl", include_str!("shaders/complex.wgsl")),
+        ("formula.wgsl", include_str!("shaders/formula.wgsl")),
+    ];
 
     /// Initializes a new GPU compute pipeline.
     ///
@@ -50,19 +112,32 @@ impl GPUPipeline {
     /// - `window`: A reference to the window used for the pipeline.
     /// - `compute_data`: The compute data to be used in the pipeline. This
     ///   struct contains the data that will be passed to the compute shader.
-    pub fn new(window: &Window, compute_data: ComputeData) -> Self {
+    /// - `newton_data`: The polynomial used by the Newton kernel.
+    /// - `orbit_trap_data`: The orbit trap used by the orbit trap coloring.
+    /// - `lyapunov_data`: The A/B sequence used by the Lyapunov kernel.
+    /// - `limit_cycle_data`: The limit cycle of the Van der Pol oscillator.
+    /// - `formulas`: The user formulas compiled into the compute shader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shaders compiled into the application are invalid.
+    pub fn new(
+        window: &Window,
+        compute_data: ComputeData,
+        newton_data: NewtonData,
+        orbit_trap_data: OrbitTrapData,
+        lyapunov_data: LyapunovData,
+        limit_cycle_data: LimitCycleData,
+        formulas: &FormulaSet,
+    ) -> Self {
         // Initialize utilities
         let device = window.device();
         let msaa_samples = window.msaa_samples();
         let (width, height) = window.inner_size_pixels();
         let processing_data = PostProcessingData::default();
 
-        // Load shader
-        let compute_shader =
-            device.create_shader_module(wgpu::include_wgsl!("shaders/compute.wgsl"));
-        let render_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/render.wgsl"));
-        let post_processing_shader =
-            device.create_shader_module(wgpu::include_wgsl!("shaders/post_processing.wgsl"));
+        // Load shaders
+        let shader_sources = Self::embedded_shader_sources();
 
         // Create texture
         let texture = Self::create_texture(device, [width, height], Self::TEXTURE_FORMAT);
@@ -79,113 +154,142 @@ impl GPUPipeline {
             contents: processing_data.as_bytes(),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         });
+        let newton_data_buffer = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("Newton Data Storage Buffer"),
+            contents: newton_data.as_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        let reference_orbit_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Reference Orbit Storage Buffer"),
+            size: (MAX_REFERENCE_ORBIT_LEN * std::mem::size_of::<[f32; 2]>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // A BLA table has at most two steps per point of the reference orbit
+        let bla_table_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("BLA Table Storage Buffer"),
+            size: (2 * MAX_REFERENCE_ORBIT_LEN * std::mem::size_of::<BlaStep>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let early_exits_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Early Exits Storage Buffer"),
+            size: std::mem::size_of::<[u32; InteriorCheck::ALL.len()]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let early_exits_readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Early Exits Readback Buffer"),
+            size: early_exits_buffer.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let orbit_trap_data_buffer = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("Orbit Trap Data Uniforms Buffer"),
+            contents: orbit_trap_data.as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let lyapunov_data_buffer = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("Lyapunov Data Storage Buffer"),
+            contents: lyapunov_data.as_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let density_buffer = Self::create_density_buffer(device, [width, height]);
+        let limit_cycle_data_buffer = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("Limit Cycle Data Storage Buffer"),
+            contents: limit_cycle_data.as_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // The image trap is transparent, hence never hit, until an image is
+        // loaded
+        let trap_image = Self::create_trap_image(device, [1, 1]);
+        let trap_image_view = trap_image.view().build();
 
         // Create the compute bind group
-        let compute_bgl = Self::create_compute_bgl(device, &texture);
+        let compute_bgl = Self::create_compute_bgl(device, &texture, &trap_image);
         let compute_bg = Self::create_compute_bg(
             device,
             &compute_bgl,
             &texture_view,
-            &compute_data_buffer,
-            &processing_data_buffer,
+            &[
+                &compute_data_buffer,
+                &processing_data_buffer,
+                &newton_data_buffer,
+                &reference_orbit_buffer,
+                &bla_table_buffer,
+                &early_exits_buffer,
+                &orbit_trap_data_buffer,
+                &lyapunov_data_buffer,
+                &density_buffer,
+                &limit_cycle_data_buffer,
+            ],
+            &trap_image_view,
         );
 
-        // Create the compute pipeline
-        let compute_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Compute Pipeline Layout"),
-                bind_group_layouts: &[&compute_bgl],
-                push_constant_ranges: &[],
-            });
-
-        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &compute_shader,
-            entry_point: "cs_main",
-        });
-
-        let min_max_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Min/Max Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &post_processing_shader,
-            entry_point: "cs_min_max",
-        });
-
-        let recalibrate_pipeline =
-            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Recalibrate Compute Pipeline"),
-                layout: Some(&compute_pipeline_layout),
-                module: &post_processing_shader,
-                entry_point: "cs_recalibrate",
-            });
-
-        let histogram_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Histogram Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &post_processing_shader,
-            entry_point: "cs_histogram",
-        });
-
-        let cdf_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("CDF Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &post_processing_shader,
-            entry_point: "cs_cdf",
-        });
-
-        let equalize_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Equalize Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &post_processing_shader,
-            entry_point: "cs_equalize",
-        });
+        // Create the compute and post-processing pipelines
+        let compute_pipeline_layout = Self::create_compute_pipeline_layout(device, &compute_bgl);
+        let compute_pipelines =
+            ComputePipelines::new(device, &compute_pipeline_layout, &shader_sources, formulas)
+                .unwrap_or_else(|error| panic!("Failed to create the compute pipelines: {error}"));
+        let post_processing_pipelines = PostProcessingPipelines::new(
+            device,
+            &compute_pipeline_layout,
+            &shader_sources,
+            formulas,
+        )
+        .unwrap_or_else(|error| panic!("Failed to create the post-processing pipelines: {error}"));
 
         // Create the render bind group
         let render_bgl = Self::create_render_bgl(device, &texture);
         let render_bg = Self::create_render_bg(device, &render_bgl, &texture_view);
 
-        // Create the pipeline layout
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&render_bgl],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline =
-            wgpu::RenderPipelineBuilder::from_layout(&render_pipeline_layout, &render_shader)
-                .vertex_entry_point("vs_main")
-                .fragment_shader(&render_shader)
-                .fragment_entry_point("fs_main")
-                .color_format(Frame::TEXTURE_FORMAT)
-                .color_blend(wgpu::BlendComponent::REPLACE)
-                .alpha_blend(wgpu::BlendComponent::REPLACE)
-                .primitive_topology(wgpu::PrimitiveTopology::TriangleList)
-                .front_face(wgpu::FrontFace::Ccw)
-                .cull_mode(Some(wgpu::Face::Back))
-                .sample_count(msaa_samples)
-                .build(device);
+        // Create the render pipeline
+        let render_pipeline = Self::create_render_pipeline(
+            device,
+            &render_bgl,
+            msaa_samples,
+            &shader_sources,
+            formulas,
+        )
+        .unwrap_or_else(|error| panic!("Failed to create the render pipeline: {error}"));
 
         GPUPipeline {
             texture,
             texture_view,
             compute_data_buffer,
             processing_data_buffer,
+            newton_data_buffer,
+            reference_orbit_buffer,
+            bla_table_buffer,
+            early_exits_buffer,
+            early_exits_readback_buffer,
+            early_exits_mapping: None,
+            early_exits_map_result: Arc::new(Mutex::new(None)),
+            early_exits_outdated: None,
+            orbit_trap_data_buffer,
+            lyapunov_data_buffer,
+            density_buffer,
+            limit_cycle_data_buffer,
+            trap_image,
+            trap_image_view,
             // Generate texture
             compute_bgl,
             compute_bg,
-            compute_pipeline,
+            compute_pipelines,
             // Post-processing
-            min_max_pipeline,
-            recalibrate_pipeline,
-            histogram_pipeline,
-            cdf_pipeline,
-            equalize_pipeline,
+            post_processing_pipelines,
             // Render
             render_bgl,
             render_bg,
             render_pipeline,
+            msaa_samples,
+            // Shaders
+            shader_sources,
+            formulas: formulas.clone(),
             enable_post_processing: true,
         }
     }
@@ -207,16 +311,35 @@ impl GPUPipeline {
         let dispatch_x = w.div_ceil(Self::WORKGROUP_SIZE);
         let dispatch_y = h.div_ceil(Self::WORKGROUP_SIZE);
 
+        // Clear the early exit counters
+        queue.write_buffer(
+            &self.early_exits_buffer,
+            0,
+            &[0; std::mem::size_of::<[u32; InteriorCheck::ALL.len()]>()],
+        );
+
         // Generate texture
         {
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("Compute Pass"),
             });
-            pass.set_pipeline(&self.compute_pipeline);
+            pass.set_pipeline(&self.compute_pipelines.main);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
         }
 
+        // Copy the early exit counters, to be read by `request_early_exits`,
+        // unless the readback buffer is still mapped for the previous ones
+        if self.early_exits_mapping.is_none() {
+            encoder.copy_buffer_to_buffer(
+                &self.early_exits_buffer,
+                0,
+                &self.early_exits_readback_buffer,
+                0,
+                self.early_exits_buffer.size(),
+            );
+        }
+
         if !self.enable_post_processing {
             return;
         }
@@ -229,37 +352,70 @@ impl GPUPipeline {
         );
 
         {
+            let pipelines = &self.post_processing_pipelines;
             let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("Get Post-Processing Pass"),
             });
 
             // Get min/max of texture
-            pass.set_pipeline(&self.min_max_pipeline);
+            pass.set_pipeline(&pipelines.min_max);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
 
             // Recalibrate texture
-            pass.set_pipeline(&self.recalibrate_pipeline);
+            pass.set_pipeline(&pipelines.recalibrate);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
 
             // Generate histogram
-            pass.set_pipeline(&self.histogram_pipeline);
+            pass.set_pipeline(&pipelines.histogram);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
 
             // Generate CDF
-            pass.set_pipeline(&self.cdf_pipeline);
+            pass.set_pipeline(&pipelines.cdf);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(1, 1, 1);
 
             // Equalize texture
-            pass.set_pipeline(&self.equalize_pipeline);
+            pass.set_pipeline(&pipelines.equalize);
             pass.set_bind_group(0, &self.compute_bg, &[]);
             pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
         }
     }
 
+    /// Dispatches the accumulation of random samples into the density of the
+    /// Buddhabrot, of the attractors or of the Poincaré sections, which must
+    /// precede [`GPUPipeline::dispatch_compute`].
+    ///
+    /// # Arguments
+    ///
+    /// - `encoder`: A mutable reference to the command encoder used for rendering.
+    /// - `num_particles`: The number of random samples to accumulate.
+    /// - `restart`: Whether to clear the density accumulated by the previous
+    ///   dispatches.
+    pub fn dispatch_density(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        num_particles: u32,
+        restart: bool,
+    ) {
+        if restart {
+            encoder.clear_buffer(&self.density_buffer, 0, None);
+        }
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Density Pass"),
+        });
+        pass.set_pipeline(&self.compute_pipelines.density);
+        pass.set_bind_group(0, &self.compute_bg, &[]);
+        pass.dispatch_workgroups(
+            num_particles.div_ceil(Self::WORKGROUP_SIZE * Self::WORKGROUP_SIZE),
+            1,
+            1,
+        );
+    }
+
     /// Dispatches the render pipeline for rendering.
     ///
     /// # Arguments
@@ -281,6 +437,100 @@ impl GPUPipeline {
         render_pass.draw(0..3, 0..1); // Draw the full-screen triangle
     }
 
+    /// Starts reading the number of pixels whose iteration was stopped early
+    /// by each interior check during the last submitted compute dispatch.
+    ///
+    /// The counters are mapped asynchronously, so that the CPU does not wait
+    /// for the GPU, and collected by [`GPUPipeline::poll_early_exits`] on a
+    /// later frame. If the previous counters are still being mapped, the new
+    /// ones are read once they are collected.
+    ///
+    /// # Arguments
+    ///
+    /// - `kernel`: The kernel of the compute dispatch.
+    pub fn request_early_exits(&mut self, kernel: Kernel) {
+        if self.early_exits_mapping.is_some() {
+            self.early_exits_outdated = Some(kernel);
+            return;
+        }
+        self.early_exits_mapping = Some(kernel);
+
+        let map_result = Arc::clone(&self.early_exits_map_result);
+        let slice = self.early_exits_readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, move |res| {
+            *map_result.lock().unwrap() = Some(res);
+        });
+    }
+
+    /// Collects the counters requested by
+    /// [`GPUPipeline::request_early_exits`] if the GPU is done with them,
+    /// without blocking.
+    ///
+    /// Counters of another kernel than `kernel` are dropped. If computes of
+    /// `kernel` were requested in the meantime, the counters of the last one
+    /// are copied and requested in turn.
+    ///
+    /// # Arguments
+    ///
+    /// - `device`: The device of the pipeline.
+    /// - `queue`: The queue used to copy the counters.
+    /// - `kernel`: The kernel currently displayed.
+    ///
+    /// # Returns
+    ///
+    /// - The number of pixels for each check of [`InteriorCheck::ALL`], or
+    ///   `None` if no counters of `kernel` are ready.
+    pub fn poll_early_exits(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        kernel: Kernel,
+    ) -> Option<[u32; InteriorCheck::ALL.len()]> {
+        let mapped_kernel = self.early_exits_mapping?;
+        device.poll(wgpu::Maintain::Poll);
+        let map_result = self.early_exits_map_result.lock().unwrap().take()?;
+
+        let mut early_exits = None;
+        match map_result {
+            Ok(()) => {
+                let mut counts = [0; InteriorCheck::ALL.len()];
+                {
+                    let data = self
+                        .early_exits_readback_buffer
+                        .slice(..)
+                        .get_mapped_range();
+                    for (count, bytes) in counts.iter_mut().zip(data.chunks_exact(4)) {
+                        *count = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    }
+                }
+                self.early_exits_readback_buffer.unmap();
+                early_exits = Some(counts).filter(|_| mapped_kernel == kernel);
+            }
+            // The device may be lost, the counters are only informative
+            Err(error) => eprintln!("Failed to read the early exit counters: {error}"),
+        }
+        self.early_exits_mapping = None;
+
+        // Read the counters which could not be copied while mapping, unless a
+        // compute of another kernel cleared them since
+        if self.early_exits_outdated.take() == Some(kernel) {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Early Exits Copy Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(
+                &self.early_exits_buffer,
+                0,
+                &self.early_exits_readback_buffer,
+                0,
+                self.early_exits_buffer.size(),
+            );
+            queue.submit(Some(encoder.finish()));
+            self.request_early_exits(kernel);
+        }
+
+        early_exits
+    }
+
     pub fn save_texture(
         &self,
         device: &wgpu::Device,
@@ -381,23 +631,41 @@ impl GPUPipeline {
         }
     }
 
-    /// Recreates the texture, its view, and the bind groups
+    /// Recreates the texture, its view, the density buffer, and the
+    /// bind groups
     pub fn resize(&mut self, device: &wgpu::Device, new_size: [u32; 2]) {
         // Recreate the texture & view
         self.texture = Self::create_texture(device, new_size, self.texture.format());
         self.texture_view = self.texture.view().build();
+        self.density_buffer = Self::create_density_buffer(device, new_size);
 
         // Rebuild the compute bind group
+        self.rebuild_compute_bg(device);
+
+        // Rebuild the render bind group
+        self.render_bg = Self::create_render_bg(device, &self.render_bgl, &self.texture_view);
+    }
+
+    /// Rebuilds the compute bind group after one of its textures changed.
+    fn rebuild_compute_bg(&mut self, device: &wgpu::Device) {
         self.compute_bg = Self::create_compute_bg(
             device,
             &self.compute_bgl,
             &self.texture_view,
-            &self.compute_data_buffer,
-            &self.processing_data_buffer,
+            &[
+                &self.compute_data_buffer,
+                &self.processing_data_buffer,
+                &self.newton_data_buffer,
+                &self.reference_orbit_buffer,
+                &self.bla_table_buffer,
+                &self.early_exits_buffer,
+                &self.orbit_trap_data_buffer,
+                &self.lyapunov_data_buffer,
+                &self.density_buffer,
+                &self.limit_cycle_data_buffer,
+            ],
+            &self.trap_image_view,
         );
-
-        // Rebuild the render bind group
-        self.render_bg = Self::create_render_bg(device, &self.render_bgl, &self.texture_view);
     }
 
     /// Updates the Compute data buffer with new data.
@@ -431,6 +699,418 @@ impl GPUPipeline {
         );
     }
 
+    /// Updates the Newton data buffer with new data.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used for the pipeline.
+    /// - `newton_data`: The new polynomial to be used by the Newton kernel.
+    pub fn update_newton_data_buffer(&self, queue: &wgpu::Queue, newton_data: &NewtonData) {
+        queue.write_buffer(&self.newton_data_buffer, 0, newton_data.as_bytes());
+    }
+
+    /// Updates the orbit trap data buffer with a new trap.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used to write the buffer.
+    /// - `orbit_trap_data`: The new orbit trap used by the orbit trap coloring.
+    pub fn update_orbit_trap_data_buffer(
+        &self,
+        queue: &wgpu::Queue,
+        orbit_trap_data: &OrbitTrapData,
+    ) {
+        queue.write_buffer(&self.orbit_trap_data_buffer, 0, orbit_trap_data.as_bytes());
+    }
+
+    /// Updates the Lyapunov data buffer with a new sequence.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used to write the buffer.
+    /// - `lyapunov_data`: The new A/B sequence used by the Lyapunov kernel.
+    pub fn update_lyapunov_data_buffer(&self, queue: &wgpu::Queue, lyapunov_data: &LyapunovData) {
+        queue.write_buffer(&self.lyapunov_data_buffer, 0, lyapunov_data.as_bytes());
+    }
+
+    /// Updates the limit cycle data buffer with a new cycle.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used to write the buffer.
+    /// - `limit_cycle_data`: The new limit cycle of the Van der Pol oscillator.
+    pub fn update_limit_cycle_data_buffer(
+        &self,
+        queue: &wgpu::Queue,
+        limit_cycle_data: &LimitCycleData,
+    ) {
+        queue.write_buffer(
+            &self.limit_cycle_data_buffer,
+            0,
+            limit_cycle_data.as_bytes(),
+        );
+    }
+
+    /// Replaces the image sampled by the image orbit trap.
+    ///
+    /// The texture is recreated to the size of the image, downscaled to the
+    /// largest texture supported by the device, so the compute bind group is
+    /// rebuilt too.
+    ///
+    /// # Returns
+    ///
+    /// - An error message if the texture cannot be created, in which case the
+    ///   previous image is kept.
+    pub fn update_trap_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::RgbaImage,
+    ) -> Result<(), String> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err("the image is empty".to_string());
+        }
+        let fitted = fit_trap_image(image, device.limits().max_texture_dimension_2d);
+        let image = fitted.as_ref().unwrap_or(image);
+
+        let (w, h) = image.dimensions();
+        self.trap_image =
+            catch_validation_errors(device, || Self::create_trap_image(device, [w, h]))?;
+        self.trap_image_view = self.trap_image.view().build();
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.trap_image,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            image.as_raw(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * w),
+                rows_per_image: Some(h),
+            },
+            self.trap_image.extent(),
+        );
+
+        self.rebuild_compute_bg(device);
+        Ok(())
+    }
+
+    /// Updates the reference orbit buffer used by the perturbation kernel.
+    ///
+    /// Only the first [`MAX_REFERENCE_ORBIT_LEN`] points of the orbit are
+    /// uploaded.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used for the pipeline.
+    /// - `reference_orbit`: The new reference orbit.
+    pub fn update_reference_orbit_buffer(&self, queue: &wgpu::Queue, reference_orbit: &[[f32; 2]]) {
+        let len = reference_orbit.len().min(MAX_REFERENCE_ORBIT_LEN);
+        let bytes = unsafe { wgpu::bytes::from_slice(&reference_orbit[..len]) };
+        queue.write_buffer(&self.reference_orbit_buffer, 0, bytes);
+    }
+
+    /// Updates the BLA table buffer used by the perturbation kernel.
+    ///
+    /// # Arguments
+    ///
+    /// - `queue`: A reference to the queue used for the pipeline.
+    /// - `steps`: The steps of the table, one level after the other.
+    pub fn update_bla_table_buffer(&self, queue: &wgpu::Queue, steps: &[BlaStep]) {
+        let len = steps.len().min(2 * MAX_REFERENCE_ORBIT_LEN);
+        let bytes = unsafe { wgpu::bytes::from_slice(&steps[..len]) };
+        queue.write_buffer(&self.bla_table_buffer, 0, bytes);
+    }
+
+    /// Recompiles the compute shader with new user formulas.
+    ///
+    /// The previous compute pipeline is kept if the formulas do not compile.
+    ///
+    /// # Arguments
+    ///
+    /// - `device`: A reference to the device used for the pipeline.
+    /// - `formulas`: The new user formulas.
+    ///
+    /// # Returns
+    ///
+    /// - An error message with the line of the shader at fault if the
+    ///   formulas do not compile.
+    pub fn update_formulas(
+        &mut self,
+        device: &wgpu::Device,
+        formulas: &FormulaSet,
+    ) -> Result<(), String> {
+        let layout = Self::create_compute_pipeline_layout(device, &self.compute_bgl);
+        self.compute_pipelines =
+            ComputePipelines::new(device, &layout, &self.shader_sources, formulas)?;
+        self.formulas = formulas.clone();
+        Ok(())
+    }
+
+    /// Returns the shader files compiled into the application by name, as
+    /// given by [`GPUPipeline::SHADER_FILES`].
+    pub fn embedded_shader_sources() -> HashMap<String, String> {
+        Self::SHADER_FILES
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.to_string()))
+            .collect()
+    }
+
+    /// Replaces the sources of shader files and rebuilds all the pipelines.
+    ///
+    /// Each pipeline whose shaders do not compile keeps its previous version,
+    /// so the last valid shaders are used until the errors are fixed.
+    ///
+    /// # Arguments
+    ///
+    /// - `device`: A reference to the device used for the pipeline.
+    /// - `sources`: The names of the shader files, as in
+    ///   [`GPUPipeline::SHADER_FILES`], with their new source.
+    ///
+    /// # Returns
+    ///
+    /// - The errors of the pipelines which could not be rebuilt, one per line.
+    pub fn update_shader_sources(
+        &mut self,
+        device: &wgpu::Device,
+        sources: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(), String> {
+        self.shader_sources.extend(sources);
+
+        let layout = Self::create_compute_pipeline_layout(device, &self.compute_bgl);
+        let errors: Vec<String> = [
+            rebuild_or_keep(&mut self.compute_pipelines, || {
+                ComputePipelines::new(device, &layout, &self.shader_sources, &self.formulas)
+            }),
+            rebuild_or_keep(&mut self.post_processing_pipelines, || {
+                PostProcessingPipelines::new(device, &layout, &self.shader_sources, &self.formulas)
+            }),
+            rebuild_or_keep(&mut self.render_pipeline, || {
+                Self::create_render_pipeline(
+                    device,
+                    &self.render_bgl,
+                    self.msaa_samples,
+                    &self.shader_sources,
+                    &self.formulas,
+                )
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Creates the preprocessor of the shaders.
+    ///
+    /// It defines the constants shared between Rust and the shaders so that
+    /// they cannot drift apart:
+    ///
+    /// - `F64` if `shader_f64` is set, which selects the precision aliases
+    ///   `float` and `vec2float`.
+    /// - `WORKGROUP_SIZE`, from [`GPUPipeline::WORKGROUP_SIZE`], and
+    ///   `WORKGROUP_INVOCATIONS`, the number of invocations per workgroup.
+    /// - `MAX_NEWTON_ROOTS`, from [`MAX_NEWTON_ROOTS`], and
+    ///   `MAX_LYAPUNOV_SEQUENCE_LEN`, from [`MAX_LYAPUNOV_SEQUENCE_LEN`].
+    /// - `BUDDHABROT_SEED`, from [`BUDDHABROT_SEED`], and `LIMIT_CYCLE_LEN`,
+    ///   from [`LIMIT_CYCLE_LEN`].
+    /// - The identifiers of the kernels, from [`Kernel::shader_constant`].
+    /// - The identifiers of the coloring modes, from
+    ///   [`Coloring::shader_constant`] and
+    ///   [`InteriorColoring::shader_constant`].
+    /// - The identifiers of the orbit trap shapes and values, from
+    ///   [`TrapShape::shader_constant`] and [`TrapValue::shader_constant`].
+    /// - The identifiers of the flow views and the colorings of the line
+    ///   integral convolution, from [`FlowView::shader_constant`] and
+    ///   [`LicColoring::shader_constant`], and `MAX_LOCKING_PERIOD` and
+    ///   `LOCKING_TOLERANCE`, from [`MAX_LOCKING_PERIOD`] and
+    ///   [`LOCKING_TOLERANCE`].
+    /// - The identifiers of the integrators, from
+    ///   [`Integrator::shader_constant`], and `MAX_REJECTED_STEPS`, from
+    ///   [`MAX_REJECTED_STEPS`].
+    /// - The bits of the interior checks, from
+    ///   [`InteriorCheck::shader_constant`], their number
+    ///   `INTERIOR_CHECK_COUNT`, and `PERIODICITY_TOLERANCE`, from
+    ///   [`PERIODICITY_TOLERANCE`].
+    ///
+    /// The shader files of `shader_sources` can be included by their name,
+    /// such as the helpers shared by the shaders in `common.wgsl`. The
+    /// functions generated from `formulas` are available as `formulas.wgsl`.
+    fn create_preprocessor(
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+        shader_f64: bool,
+    ) -> Preprocessor {
+        let mut preprocessor = Preprocessor::new();
+        if shader_f64 {
+            preprocessor.define("F64", "");
+        }
+        preprocessor.define("WORKGROUP_SIZE", format!("{}u", Self::WORKGROUP_SIZE));
+        preprocessor.define(
+            "WORKGROUP_INVOCATIONS",
+            format!("{}u", Self::WORKGROUP_SIZE * Self::WORKGROUP_SIZE),
+        );
+        preprocessor.define("MAX_NEWTON_ROOTS", format!("{MAX_NEWTON_ROOTS}u"));
+        preprocessor.define(
+            "MAX_LYAPUNOV_SEQUENCE_LEN",
+            format!("{MAX_LYAPUNOV_SEQUENCE_LEN}u"),
+        );
+        preprocessor.define("BUDDHABROT_SEED", format!("{BUDDHABROT_SEED}u"));
+        preprocessor.define("LIMIT_CYCLE_LEN", format!("{LIMIT_CYCLE_LEN}u"));
+        for kernel in Kernel::ALL {
+            preprocessor.define(kernel.shader_constant(), format!("{}u", kernel as u32));
+        }
+        for coloring in Coloring::ALL {
+            preprocessor.define(coloring.shader_constant(), format!("{}u", coloring as u32));
+        }
+        for coloring in InteriorColoring::ALL {
+            preprocessor.define(coloring.shader_constant(), format!("{}u", coloring as u32));
+        }
+        for shape in TrapShape::ALL {
+            preprocessor.define(shape.shader_constant(), format!("{}u", shape as u32));
+        }
+        for value in TrapValue::ALL {
+            preprocessor.define(value.shader_constant(), format!("{}u", value as u32));
+        }
+        for view in FlowView::ALL {
+            preprocessor.define(view.shader_constant(), format!("{}u", view as u32));
+        }
+        for coloring in LicColoring::ALL {
+            preprocessor.define(coloring.shader_constant(), format!("{}u", coloring as u32));
+        }
+        preprocessor.define("MAX_LOCKING_PERIOD", format!("{MAX_LOCKING_PERIOD}u"));
+        preprocessor.define("LOCKING_TOLERANCE", format!("{LOCKING_TOLERANCE:e}"));
+        for integrator in Integrator::ALL {
+            preprocessor.define(
+                integrator.shader_constant(),
+                format!("{}u", integrator as u32),
+            );
+        }
+        preprocessor.define("MAX_REJECTED_STEPS", format!("{MAX_REJECTED_STEPS}u"));
+        for check in InteriorCheck::ALL {
+            preprocessor.define(check.shader_constant(), format!("{}u", check as u32));
+        }
+        preprocessor.define(
+            "INTERIOR_CHECK_COUNT",
+            format!("{}u", InteriorCheck::ALL.len()),
+        );
+        preprocessor.define(
+            "PERIODICITY_TOLERANCE",
+            format!("{PERIODICITY_TOLERANCE:e}"),
+        );
+        for (name, source) in shader_sources {
+            preprocessor.add_include(name, source);
+        }
+        preprocessor.add_include("formulas.wgsl", &formulas.to_wgsl());
+        preprocessor
+    }
+
+    /// Preprocesses a shader file and validates it with naga.
+    ///
+    /// # Arguments
+    ///
+    /// - `shader_sources`: The shader files by name, such as
+    ///   [`GPUPipeline::SHADER_FILES`].
+    /// - `formulas`: The formulas available to the shader as `formulas.wgsl`.
+    /// - `name`: The name of the shader file to preprocess.
+    /// - `shader_f64`: Whether the shader computes in `f64`, as it does with
+    ///   the `f64` feature.
+    ///
+    /// # Returns
+    ///
+    /// - The preprocessed source, or an error message with the file and line
+    ///   at fault if the shader is invalid.
+    pub fn preprocess_shader(
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+        name: &str,
+        shader_f64: bool,
+    ) -> Result<String, String> {
+        let source = shader_sources
+            .get(name)
+            .ok_or_else(|| format!("{name}: missing shader"))?;
+        let (source, lines) = Self::create_preprocessor(shader_sources, formulas, shader_f64)
+            .process_mapped(name, source)?;
+        validate_shader(name, &source, &lines)?;
+        Ok(source)
+    }
+
+    /// Preprocesses and validates a shader file, then creates its module.
+    ///
+    /// # Returns
+    ///
+    /// - An error message with the file and line at fault if the shader is
+    ///   invalid.
+    fn create_shader_module(
+        device: &wgpu::Device,
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+        name: &str,
+    ) -> Result<wgpu::ShaderModule, String> {
+        let source =
+            Self::preprocess_shader(shader_sources, formulas, name, cfg!(feature = "f64"))?;
+
+        catch_validation_errors(device, || {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(name),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            })
+        })
+        .map_err(|error| format!("{name}: {error}"))
+    }
+
+    /// Creates the layout shared by the compute and post-processing pipelines.
+    fn create_compute_pipeline_layout(
+        device: &wgpu::Device,
+        compute_bgl: &wgpu::BindGroupLayout,
+    ) -> wgpu::PipelineLayout {
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compute Pipeline Layout"),
+            bind_group_layouts: &[compute_bgl],
+            push_constant_ranges: &[],
+        })
+    }
+
+    /// Creates the render pipeline drawing the texture to the frame.
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        render_bgl: &wgpu::BindGroupLayout,
+        msaa_samples: u32,
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+    ) -> Result<wgpu::RenderPipeline, String> {
+        let shader = Self::create_shader_module(device, shader_sources, formulas, "render.wgsl")?;
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[render_bgl],
+            push_constant_ranges: &[],
+        });
+
+        catch_validation_errors(device, || {
+            wgpu::RenderPipelineBuilder::from_layout(&layout, &shader)
+                .vertex_entry_point("vs_main")
+                .fragment_shader(&shader)
+                .fragment_entry_point("fs_main")
+                .color_format(Frame::TEXTURE_FORMAT)
+                .color_blend(wgpu::BlendComponent::REPLACE)
+                .alpha_blend(wgpu::BlendComponent::REPLACE)
+                .primitive_topology(wgpu::PrimitiveTopology::TriangleList)
+                .front_face(wgpu::FrontFace::Ccw)
+                .cull_mode(Some(wgpu::Face::Back))
+                .sample_count(msaa_samples)
+                .build(device)
+        })
+        .map_err(|error| format!("render.wgsl: {error}"))
+    }
+
     /// Creates a new texture for the compute and render pipelines.
     fn create_texture(
         device: &wgpu::Device,
@@ -452,8 +1132,34 @@ impl GPUPipeline {
             .build(device)
     }
 
+    /// Creates the density buffer for a texture of the given size.
+    fn create_density_buffer(device: &wgpu::Device, size: [u32; 2]) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Density Storage Buffer"),
+            size: (3 * size[0] * size[1]) as u64 * std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Creates the texture of the image sampled by the image orbit trap.
+    fn create_trap_image(device: &wgpu::Device, size: [u32; 2]) -> wgpu::Texture {
+        wgpu::TextureBuilder::new()
+            .size(size)
+            .dimension(wgpu::TextureDimension::D2)
+            .mip_level_count(1)
+            .sample_count(1)
+            .format(Self::TRAP_IMAGE_FORMAT)
+            .usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST)
+            .build(device)
+    }
+
     /// Creates a new bind group layout for the compute pipeline.
-    fn create_compute_bgl(device: &wgpu::Device, texture: &wgpu::Texture) -> wgpu::BindGroupLayout {
+    fn create_compute_bgl(
+        device: &wgpu::Device,
+        texture: &wgpu::Texture,
+        trap_image: &wgpu::Texture,
+    ) -> wgpu::BindGroupLayout {
         wgpu::BindGroupLayoutBuilder::new()
             .storage_texture(
                 wgpu::ShaderStages::COMPUTE,
@@ -463,21 +1169,37 @@ impl GPUPipeline {
             )
             .uniform_buffer(wgpu::ShaderStages::COMPUTE, false)
             .storage_buffer(wgpu::ShaderStages::COMPUTE, false, false)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, false)
+            .uniform_buffer(wgpu::ShaderStages::COMPUTE, false)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, false)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .texture_from(wgpu::ShaderStages::COMPUTE, trap_image)
             .build(device)
     }
 
     /// Creates a new bind group for the compute pipeline.
+    ///
+    /// The buffers are bound after the texture, in the order of their
+    /// bindings in the compute shader, followed by the image of the orbit
+    /// trap.
     fn create_compute_bg(
         device: &wgpu::Device,
         compute_bgl: &wgpu::BindGroupLayout,
         texture_view: &wgpu::TextureView,
-        compute_data_buffer: &wgpu::Buffer,
-        processing_data_buffer: &wgpu::Buffer,
+        buffers: &[&wgpu::Buffer],
+        trap_image_view: &wgpu::TextureView,
     ) -> wgpu::BindGroup {
-        wgpu::BindGroupBuilder::new()
-            .texture_view(texture_view)
-            .binding(compute_data_buffer.as_entire_binding())
-            .binding(processing_data_buffer.as_entire_binding())
+        buffers
+            .iter()
+            .fold(
+                wgpu::BindGroupBuilder::new().texture_view(texture_view),
+                |builder, buffer| builder.binding(buffer.as_entire_binding()),
+            )
+            .texture_view(trap_image_view)
             .build(device, compute_bgl)
     }
 
@@ -509,3 +1231,168 @@ impl GPUPipeline {
         self.texture.extent()
     }
 }
+
+/// Parses and validates a WGSL shader with naga, which wgpu would otherwise
+/// treat as a fatal error.
+///
+/// # Arguments
+///
+/// - `name`: The name of the preprocessed shader file.
+/// - `source`: The preprocessed source of the shader.
+/// - `lines`: The origin of each line of `source`.
+///
+/// # Returns
+///
+/// - An error message with the file and line at fault if the shader is
+///   invalid.
+fn validate_shader(name: &str, source: &str, lines: &[SourceLine]) -> Result<(), String> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|error| {
+        locate_error(name, source, lines, error.location(source), error.message())
+    })?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| {
+        // The inner error only names the faulty function, its causes tell why
+        let mut message = error.as_inner().to_string();
+        let mut cause = std::error::Error::source(error.as_inner());
+        while let Some(inner) = cause {
+            message = format!("{message}: {inner}");
+            cause = inner.source();
+        }
+        locate_error(name, source, lines, error.location(source), &message)
+    })?;
+
+    Ok(())
+}
+
+/// Formats an error of a preprocessed shader with the file and line it comes
+/// from, quoting the preprocessed line.
+fn locate_error(
+    name: &str,
+    source: &str,
+    lines: &[SourceLine],
+    location: Option<naga::SourceLocation>,
+    message: &str,
+) -> String {
+    let Some(location) = location else {
+        return format!("{name}: {message}");
+    };
+    let index = location.line_number as usize - 1;
+    let line = source.lines().nth(index).unwrap_or_default();
+    match lines.get(index) {
+        Some(origin) => format!(
+            "{}:{}: {message}\n    {}",
+            origin.file,
+            origin.line,
+            line.trim()
+        ),
+        None => format!("{name}: {message}\n    {}", line.trim()),
+    }
+}
+
+/// Pipelines of the compute shader, sharing the compute pipeline layout.
+struct ComputePipelines {
+    /// Generates the texture.
+    main: wgpu::ComputePipeline,
+    /// Accumulates random samples into the density of the Buddhabrot or of
+    /// the attractors.
+    density: wgpu::ComputePipeline,
+}
+
+impl ComputePipelines {
+    /// Creates the compute pipelines from `compute.wgsl`.
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+    ) -> Result<Self, String> {
+        let shader =
+            GPUPipeline::create_shader_module(device, shader_sources, formulas, "compute.wgsl")?;
+        let create_pipeline = |label, entry_point| {
+            catch_validation_errors(device, || {
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(layout),
+                    module: &shader,
+                    entry_point,
+                })
+            })
+            .map_err(|error| format!("compute.wgsl: {error}"))
+        };
+
+        Ok(Self {
+            main: create_pipeline("Compute Pipeline", "cs_main")?,
+            density: create_pipeline("Density Compute Pipeline", "cs_density")?,
+        })
+    }
+}
+
+/// Pipelines of the post-processing passes, which share the same shader.
+struct PostProcessingPipelines {
+    min_max: wgpu::ComputePipeline,
+    recalibrate: wgpu::ComputePipeline,
+    histogram: wgpu::ComputePipeline,
+    cdf: wgpu::ComputePipeline,
+    equalize: wgpu::ComputePipeline,
+}
+
+impl PostProcessingPipelines {
+    /// Creates the post-processing pipelines from `post_processing.wgsl`.
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader_sources: &HashMap<String, String>,
+        formulas: &FormulaSet,
+    ) -> Result<Self, String> {
+        let shader = GPUPipeline::create_shader_module(
+            device,
+            shader_sources,
+            formulas,
+            "post_processing.wgsl",
+        )?;
+        let create_pipeline = |label, entry_point| {
+            catch_validation_errors(device, || {
+                device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(layout),
+                    module: &shader,
+                    entry_point,
+                })
+            })
+            .map_err(|error| format!("post_processing.wgsl: {error}"))
+        };
+
+        Ok(Self {
+            min_max: create_pipeline("Min/Max Compute Pipeline", "cs_min_max")?,
+            recalibrate: create_pipeline("Recalibrate Compute Pipeline", "cs_recalibrate")?,
+            histogram: create_pipeline("Histogram Compute Pipeline", "cs_histogram")?,
+            cdf: create_pipeline("CDF Compute Pipeline", "cs_cdf")?,
+            equalize: create_pipeline("Equalize Compute Pipeline", "cs_equalize")?,
+        })
+    }
+}
+
+/// Runs `create`, returning the validation error it raised instead of letting
+/// wgpu treat it as fatal.
+///
+/// This catches the errors that naga cannot see in the shader alone, such as
+/// bindings which do not match the bind group layout.
+fn catch_validation_errors<T>(
+    device: &wgpu::Device,
+    create: impl FnOnce() -> T,
+) -> Result<T, String> {
+    device.push_error_scope(::wgpu::ErrorFilter::Validation);
+    let value = create();
+
+    // Native devices report errors synchronously, so the future is ready
+    let mut error = std::pin::pin!(device.pop_error_scope());
+    match error.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(Some(error)) => Err(error.to_string()),
+        _ => Ok(value),
+    }
+}
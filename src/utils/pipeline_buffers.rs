@@ -1,6 +1,28 @@
 use nannou::wgpu;
 
-use crate::{FloatChoice, INITIAL_X_RANGE, INITIAL_Y_RANGE};
+use super::{
+    coloring::{Coloring, InteriorColoring},
+    double_single::{join_double_single, split_double_single},
+    flow::{FlowView, LicColoring},
+    integrator::{INITIAL_TOLERANCE, Integrator},
+    interior::InteriorCheck,
+    kernels::Kernel,
+    limit_cycle::van_der_pol_cycle,
+    orbit_trap::{TrapShape, TrapValue},
+};
+use crate::{FloatChoice, INITIAL_JULIA_C, MAX_SHADER_ZOOM_DELTA, MAX_ZOOM_DELTA, ShaderFloat};
+
+/// Maximum number of roots of the polynomial used by the Newton kernel.
+pub const MAX_NEWTON_ROOTS: usize = 8;
+/// Maximum length of the reference orbit used by the perturbation kernel.
+pub const MAX_REFERENCE_ORBIT_LEN: usize = 1 << 17;
+/// Seed of the random samples of the Buddhabrot, the attractors and the
+/// Poincaré sections, fixed so that renders are reproducible.
+pub const BUDDHABROT_SEED: u32 = 0x2545_f491;
+/// Maximum length of the A/B sequence used by the Lyapunov kernel.
+pub const MAX_LYAPUNOV_SEQUENCE_LEN: usize = 64;
+/// Number of points of the limit cycle of the Van der Pol oscillator.
+pub const LIMIT_CYCLE_LEN: usize = 256;
 
 // This struct is passed to the GPU as a uniform buffer
 // See alignment rules for the GPU:
@@ -9,31 +31,148 @@ use crate::{FloatChoice, INITIAL_X_RANGE, INITIAL_Y_RANGE};
 #[derive(Clone, Copy)]
 pub struct ComputeData {
     pub max_iter: u32,
+    /// Number of random samples of the Buddhabrot, the attractors and the
+    /// Poincaré sections drawn at each compute
     pub num_particles: u32,
-    _padding: [u32; 2], // Needed to align the vec2<f64> to 16 bytes
-    pub dt: FloatChoice,
-    pub mu: FloatChoice,
+    pub kernel: Kernel,
+    /// Exponent of the Multibrot formula
+    pub exponent: f32,
+    /// Bailout radius of the escape-time formulas
+    pub bailout: f32,
+    /// Parameter p of the Phoenix formula
+    pub phoenix_p: f32,
+    /// Power of two exponent of `delta_span`
+    delta_exponent: i32,
+    /// Length of the reference orbit used for perturbation
+    reference_len: u32,
+    /// Size of the view for perturbation, scaled by 2^-delta_exponent
+    delta_span: [f32; 2],
+    /// Number of levels of the BLA table, 0 to disable it
+    bla_levels: u32,
+    /// Whether the Mandelbrot kernel uses double-single arithmetic
+    double_single: u32,
+    pub dt: ShaderFloat,
+    pub mu: ShaderFloat,
     /// Initial render range in x for function
-    x_range: [FloatChoice; 2],
+    x_range: [ShaderFloat; 2],
     /// Initial render range in y for function
-    y_range: [FloatChoice; 2],
+    y_range: [ShaderFloat; 2],
+    /// Constant c of the Julia set
+    julia_c: [ShaderFloat; 2],
+    /// Low parts of the x range, for double-single arithmetic
+    x_range_lo: [f32; 2],
+    /// Low parts of the y range, for double-single arithmetic
+    y_range_lo: [f32; 2],
+    /// Coloring mode of the escape-time kernels
+    coloring: Coloring,
+    /// Thickness in pixels of the boundary drawn by distance estimation
+    pub thickness: f32,
+    /// Mask of the enabled interior checks of the Mandelbrot and Julia sets
+    interior_checks: u32,
+    /// Coloring mode of the points inside the escape-time sets
+    pub interior_coloring: InteriorColoring,
+    /// Frequency of the stripes of the stripe average coloring
+    pub stripe_frequency: f32,
+    /// Number of times the colors cycle over the range of the averages of
+    /// the stripe and triangle inequality colorings
+    pub color_density: f32,
+    /// Number of computes whose samples were accumulated into the density,
+    /// which selects the samples of the next compute
+    accumulated_frames: u32,
+    /// Parameters of the attractors and the dynamical systems, named by
+    /// [`Kernel::parameters`]
+    pub params: [f32; 4],
+    /// Rendering mode of the flow kernels
+    pub flow_view: FlowView,
+    /// Number of steps traced along the streamlines in each direction by the
+    /// line integral convolution
+    pub lic_length: u32,
+    /// Coloring of the line integral convolution
+    pub lic_coloring: LicColoring,
+    /// Integrator of the ODEs of the flow kernels
+    pub integrator: Integrator,
+    /// Tolerance of the adaptive integrator, relative to the magnitude of the
+    /// integrated point
+    pub tolerance: f32,
+    /// Distance to the limit cycle of the Van der Pol oscillator below which
+    /// a trajectory is considered to have converged to it
+    pub cycle_tolerance: f32,
+    /// Amplitude of the periodic forcing of the forced oscillators
+    pub forcing_amplitude: f32,
+    /// Angular frequency of the periodic forcing of the forced oscillators
+    pub forcing_frequency: f32,
+    /// Phase of the forcing at which the Poincaré section samples the
+    /// trajectories, as a fraction of its period
+    pub section_phase: f32,
 }
 
 impl Default for ComputeData {
     fn default() -> Self {
-        Self {
+        Self::for_kernel(Kernel::Mandelbrot)
+    }
+}
+
+impl ComputeData {
+    /// Returns the initial compute data for the given kernel.
+    pub fn for_kernel(kernel: Kernel) -> Self {
+        let (x_range, y_range) = kernel.initial_ranges();
+        let mut compute_data = Self {
             max_iter: 100,
             num_particles: 20_000,
-            _padding: [0; 2],
+            kernel,
+            exponent: 3.0,
+            bailout: kernel.initial_bailout(),
+            phoenix_p: -0.5,
+            delta_exponent: 0,
+            reference_len: 0,
+            delta_span: [0.0; 2],
+            bla_levels: 0,
+            double_single: cfg!(feature = "double-single") as u32,
             dt: 0.1,
             mu: 4.5,
-            x_range: INITIAL_X_RANGE,
-            y_range: INITIAL_Y_RANGE,
+            x_range: [0.0; 2],
+            y_range: [0.0; 2],
+            julia_c: [0.0; 2],
+            x_range_lo: [0.0; 2],
+            y_range_lo: [0.0; 2],
+            coloring: Coloring::Banded,
+            thickness: 1.0,
+            interior_checks: InteriorCheck::ALL_MASK,
+            interior_coloring: InteriorColoring::Black,
+            stripe_frequency: 5.0,
+            color_density: 1.0,
+            accumulated_frames: 0,
+            params: kernel.initial_parameters(),
+            flow_view: FlowView::Escape,
+            lic_length: 20,
+            lic_coloring: LicColoring::Gray,
+            integrator: Integrator::Euler,
+            tolerance: INITIAL_TOLERANCE,
+            cycle_tolerance: 0.05,
+            forcing_amplitude: 1.0,
+            forcing_frequency: 1.0,
+            section_phase: 0.0,
+        };
+        match kernel {
+            // The Lorenz flow needs smaller steps than the Van der Pol
+            // oscillator
+            Kernel::Lorenz => compute_data.dt = 0.005,
+            // The Arnold tongues of the forced Van der Pol oscillator are
+            // clearest for a moderate mu, and the forced Duffing oscillator
+            // starts in its chaotic regime
+            Kernel::ForcedVanDerPol => compute_data.mu = 1.0,
+            Kernel::ForcedDuffing => {
+                compute_data.forcing_amplitude = 0.5;
+                compute_data.forcing_frequency = 1.2;
+            }
+            _ => {}
         }
+        compute_data.update_x_range((x_range[0], x_range[1]));
+        compute_data.update_y_range((y_range[0], y_range[1]));
+        compute_data.update_julia_c((INITIAL_JULIA_C[0], INITIAL_JULIA_C[1]));
+        compute_data
     }
-}
 
-impl ComputeData {
     /// Returns the struct as a byte slice.
     pub fn as_bytes(&self) -> &[u8] {
         unsafe { wgpu::bytes::from(self) }
@@ -41,22 +180,138 @@ impl ComputeData {
 
     /// Gets the number x_range as a tuple.
     pub fn get_x_range(&self) -> (FloatChoice, FloatChoice) {
-        (self.x_range[0], self.x_range[1])
+        (
+            join_double_single(self.x_range[0], self.x_range_lo[0]),
+            join_double_single(self.x_range[1], self.x_range_lo[1]),
+        )
     }
 
     /// Gets the number y_range as a tuple.
     pub fn get_y_range(&self) -> (FloatChoice, FloatChoice) {
-        (self.y_range[0], self.y_range[1])
+        (
+            join_double_single(self.y_range[0], self.y_range_lo[0]),
+            join_double_single(self.y_range[1], self.y_range_lo[1]),
+        )
     }
 
     /// Updates the the x_range field of the struct.
     pub fn update_x_range(&mut self, x_range: (FloatChoice, FloatChoice)) {
-        self.x_range = [x_range.0, x_range.1];
+        let (x0, x0_lo) = split_double_single(x_range.0);
+        let (x1, x1_lo) = split_double_single(x_range.1);
+        self.x_range = [x0, x1];
+        self.x_range_lo = [x0_lo, x1_lo];
     }
 
     /// Updates the the y_range field of the struct.
     pub fn update_y_range(&mut self, y_range: (FloatChoice, FloatChoice)) {
-        self.y_range = [y_range.0, y_range.1];
+        let (y0, y0_lo) = split_double_single(y_range.0);
+        let (y1, y1_lo) = split_double_single(y_range.1);
+        self.y_range = [y0, y1];
+        self.y_range_lo = [y0_lo, y1_lo];
+    }
+
+    /// Updates the fields used by the perturbation kernel.
+    ///
+    /// # Arguments
+    ///
+    /// - `delta_span`: The size of the view, scaled by 2^-`delta_exponent`.
+    /// - `delta_exponent`: The power of two exponent of `delta_span`.
+    /// - `reference_len`: The length of the reference orbit.
+    /// - `bla_levels`: The number of levels of the BLA table, 0 to disable it.
+    pub fn update_perturbation(
+        &mut self,
+        delta_span: [f32; 2],
+        delta_exponent: i32,
+        reference_len: u32,
+        bla_levels: u32,
+    ) {
+        self.delta_span = delta_span;
+        self.delta_exponent = delta_exponent;
+        self.reference_len = reference_len;
+        self.bla_levels = bla_levels;
+    }
+
+    /// Returns whether the compute shader renders with double-single
+    /// arithmetic, which it only does for the Mandelbrot kernel with the
+    /// colorings that do not follow the derivative or the terms of the orbit.
+    pub fn uses_double_single(&self) -> bool {
+        self.double_single != 0
+            && self.kernel == Kernel::Mandelbrot
+            && matches!(self.coloring, Coloring::Banded | Coloring::Smooth)
+    }
+
+    /// Returns the smallest view size that the kernel renders without
+    /// floating point errors.
+    pub fn max_zoom_delta(&self) -> FloatChoice {
+        if self.uses_double_single() {
+            MAX_ZOOM_DELTA
+        } else {
+            MAX_SHADER_ZOOM_DELTA
+        }
+    }
+
+    /// Gets the coloring mode of the escape-time kernels.
+    pub fn get_coloring(&self) -> Coloring {
+        self.coloring
+    }
+
+    /// Updates the coloring mode, raising the bailout radius to the smallest
+    /// one supported by the mode.
+    pub fn update_coloring(&mut self, coloring: Coloring) {
+        self.coloring = coloring;
+        self.bailout = self.bailout.max(coloring.min_bailout());
+    }
+
+    /// Gets the mask of the enabled interior checks.
+    pub fn get_interior_checks(&self) -> u32 {
+        self.interior_checks
+    }
+
+    /// Returns whether an interior check is enabled.
+    pub fn is_interior_check_enabled(&self, check: InteriorCheck) -> bool {
+        check.is_enabled(self.interior_checks)
+    }
+
+    /// Enables or disables an interior check.
+    pub fn set_interior_check(&mut self, check: InteriorCheck, enabled: bool) {
+        if enabled {
+            self.interior_checks |= check as u32;
+        } else {
+            self.interior_checks &= !(check as u32);
+        }
+    }
+
+    /// Returns whether the compute accumulates random samples into a density,
+    /// which the density kernels and the Poincaré section of the forced
+    /// oscillators do.
+    pub fn accumulates_density(&self) -> bool {
+        self.kernel.accumulates_density()
+            || (self.kernel.is_forced() && self.flow_view == FlowView::PoincareSection)
+    }
+
+    /// Restarts the accumulation of the density samples, so that the next
+    /// compute draws the first samples again.
+    pub fn reset_accumulation(&mut self) {
+        self.accumulated_frames = 0;
+    }
+
+    /// Moves on to the next density samples, accumulated with the
+    /// previous ones.
+    pub fn next_accumulation(&mut self) {
+        self.accumulated_frames += 1;
+    }
+
+    /// Gets the Julia set constant c as a tuple.
+    pub fn get_julia_c(&self) -> (FloatChoice, FloatChoice) {
+        (
+            self.julia_c[0] as FloatChoice,
+            self.julia_c[1] as FloatChoice,
+        )
+    }
+
+    /// Updates the the julia_c field of the struct.
+    pub fn update_julia_c(&mut self, julia_c: (FloatChoice, FloatChoice)) {
+        self.julia_c = [julia_c.0 as ShaderFloat, julia_c.1 as ShaderFloat];
     }
 }
 
@@ -94,3 +349,225 @@ impl PostProcessingData {
         unsafe { wgpu::bytes::from(self) }
     }
 }
+
+// This struct is passed to the GPU as a storage buffer
+// See alignment rules for the GPU:
+// https://www.w3.org/TR/WGSL/#alignment-and-size
+#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+pub struct NewtonData {
+    num_roots: u32,
+    /// Relaxation factor applied to each Newton step
+    pub relaxation: f32,
+    /// Roots of the polynomial, only the first `num_roots` are used
+    roots: [[f32; 2]; MAX_NEWTON_ROOTS],
+}
+
+impl Default for NewtonData {
+    fn default() -> Self {
+        // Roots of z^3 - 1
+        let mut newton_data = Self {
+            num_roots: 0,
+            relaxation: 1.0,
+            roots: [[0.0; 2]; MAX_NEWTON_ROOTS],
+        };
+        newton_data.add_root([1.0, 0.0]);
+        newton_data.add_root([-0.5, 0.866_025_4]);
+        newton_data.add_root([-0.5, -0.866_025_4]);
+        newton_data
+    }
+}
+
+impl NewtonData {
+    /// Returns the struct as a byte slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { wgpu::bytes::from(self) }
+    }
+
+    /// Gets the roots of the polynomial.
+    pub fn roots(&self) -> &[[f32; 2]] {
+        &self.roots[..self.num_roots as usize]
+    }
+
+    /// Gets the roots of the polynomial as a mutable slice.
+    pub fn roots_mut(&mut self) -> &mut [[f32; 2]] {
+        &mut self.roots[..self.num_roots as usize]
+    }
+
+    /// Adds a root to the polynomial.
+    ///
+    /// The root is ignored if the polynomial already has
+    /// [`MAX_NEWTON_ROOTS`] roots.
+    pub fn add_root(&mut self, root: [f32; 2]) {
+        let n = self.num_roots as usize;
+        if n < MAX_NEWTON_ROOTS {
+            self.roots[n] = root;
+            self.num_roots += 1;
+        }
+    }
+
+    /// Removes the root at the given index from the polynomial.
+    pub fn remove_root(&mut self, index: usize) {
+        let n = self.num_roots as usize;
+        if index < n {
+            self.roots.copy_within(index + 1..n, index);
+            self.num_roots -= 1;
+        }
+    }
+}
+
+// This struct is passed to the GPU as a uniform buffer
+// See alignment rules for the GPU:
+// https://www.w3.org/TR/WGSL/#alignment-and-size
+#[repr(C, align(16))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct OrbitTrapData {
+    /// Shape of the trap
+    pub shape: TrapShape,
+    /// Value of the orbit which colors the pixels
+    pub value: TrapValue,
+    /// Center of the trap
+    pub center: [f32; 2],
+    /// Angle of the lines of the line and cross traps, in radians
+    pub angle: f32,
+    /// Radius within which the orbit hits the trap, which also scales the
+    /// distances and half the side of the image trap
+    pub radius: f32,
+}
+
+impl Default for OrbitTrapData {
+    fn default() -> Self {
+        Self {
+            shape: TrapShape::Point,
+            value: TrapValue::MinDistance,
+            center: [0.0, 0.0],
+            angle: 0.0,
+            radius: 0.5,
+        }
+    }
+}
+
+impl OrbitTrapData {
+    /// Returns the struct as a byte slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { wgpu::bytes::from(self) }
+    }
+}
+
+// This struct is passed to the GPU as a storage buffer
+// See alignment rules for the GPU:
+// https://www.w3.org/TR/WGSL/#alignment-and-size
+#[repr(C, align(4))]
+#[derive(Clone, Copy)]
+pub struct LyapunovData {
+    sequence_len: u32,
+    /// Number of iterations of the logistic map before the Lyapunov exponent
+    /// is estimated, so that the orbit settles on its attractor
+    pub warmup: u32,
+    /// Parameter used at each step of the sequence, 0 for a and 1 for b, only
+    /// the first `sequence_len` are used
+    sequence: [u32; MAX_LYAPUNOV_SEQUENCE_LEN],
+}
+
+impl Default for LyapunovData {
+    fn default() -> Self {
+        let mut lyapunov_data = Self {
+            sequence_len: 0,
+            warmup: 100,
+            sequence: [0; MAX_LYAPUNOV_SEQUENCE_LEN],
+        };
+        lyapunov_data
+            .update_sequence("AB")
+            .expect("The default sequence is valid");
+        lyapunov_data
+    }
+}
+
+impl LyapunovData {
+    /// Returns the struct as a byte slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { wgpu::bytes::from(self) }
+    }
+
+    /// Gets the A/B sequence as a string.
+    pub fn get_sequence(&self) -> String {
+        self.sequence[..self.sequence_len as usize]
+            .iter()
+            .map(|&step| if step == 0 { 'A' } else { 'B' })
+            .collect()
+    }
+
+    /// Updates the A/B sequence from a string such as "AABAB".
+    ///
+    /// Letters are case-insensitive and whitespace is ignored. The sequence
+    /// is kept unchanged if the string is invalid.
+    ///
+    /// # Returns
+    ///
+    /// - An error message if the string contains other letters, is empty, or
+    ///   is longer than [`MAX_LYAPUNOV_SEQUENCE_LEN`].
+    pub fn update_sequence(&mut self, sequence: &str) -> Result<(), String> {
+        let steps = sequence
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c.to_ascii_uppercase() {
+                'A' => Ok(0),
+                'B' => Ok(1),
+                _ => Err(format!("Invalid letter '{c}', expected A or B")),
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+
+        if steps.is_empty() {
+            return Err("The sequence is empty".to_string());
+        }
+        if steps.len() > MAX_LYAPUNOV_SEQUENCE_LEN {
+            return Err(format!(
+                "The sequence is longer than {MAX_LYAPUNOV_SEQUENCE_LEN} letters"
+            ));
+        }
+
+        self.sequence[..steps.len()].copy_from_slice(&steps);
+        self.sequence_len = steps.len() as u32;
+        Ok(())
+    }
+}
+
+// This struct is passed to the GPU as a storage buffer
+// See alignment rules for the GPU:
+// https://www.w3.org/TR/WGSL/#alignment-and-size
+#[repr(C, align(8))]
+#[derive(Clone, Copy)]
+pub struct LimitCycleData {
+    /// Duration of one turn of the cycle
+    period: f32,
+    /// Parameter mu of the oscillator whose cycle is stored
+    mu: f32,
+    /// Points of the cycle, sampled at regular times along one period
+    points: [[f32; 2]; LIMIT_CYCLE_LEN],
+}
+
+impl LimitCycleData {
+    /// Computes the limit cycle of the Van der Pol oscillator for mu.
+    pub fn for_mu(mu: f32) -> Self {
+        let cycle = van_der_pol_cycle(mu as f64, LIMIT_CYCLE_LEN);
+        let mut points = [[0.0; 2]; LIMIT_CYCLE_LEN];
+        for (point, cycle_point) in points.iter_mut().zip(&cycle.points) {
+            *point = cycle_point.map(|coordinate| coordinate as f32);
+        }
+        Self {
+            period: cycle.period as f32,
+            mu,
+            points,
+        }
+    }
+
+    /// Returns the struct as a byte slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { wgpu::bytes::from(self) }
+    }
+
+    /// Gets the parameter mu of the oscillator whose cycle is stored.
+    pub fn get_mu(&self) -> f32 {
+        self.mu
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+/// Maximum depth of nested includes, to catch include cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Minimal preprocessor for WGSL shaders.
+///
+/// It supports the following directives, which must be alone on their line:
+///
+/// - `#define NAME [value]`: Defines `NAME`, which is then replaced by `value`
+///   wherever it appears as an identifier outside of comments.
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif`: Only keeps the lines
+///   of a block if `NAME` is, or is not, defined.
+/// - `#include "name"`: Inserts the preprocessed source of an include
+///   registered with [`Preprocessor::add_include`].
+///
+/// Directives and discarded lines are replaced by empty lines, and
+/// [`Preprocessor::process_mapped`] records the file and line each output line
+/// comes from, so that errors reported by the shader compiler can point at the
+/// original sources despite the includes.
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+    defines: HashMap<String, String>,
+    includes: HashMap<String, String>,
+}
+
+/// Origin of a line of a preprocessed shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    /// Name of the file the line comes from.
+    pub file: String,
+    /// Number of the line in its file, starting at 1.
+    pub line: usize,
+}
+
+/// State of a conditional block.
+struct Conditional {
+    /// Whether the lines of the current branch are kept.
+    active: bool,
+    /// Whether the enclosing block is active.
+    parent_active: bool,
+    /// Whether the `#else` branch was reached.
+    in_else: bool,
+}
+
+impl Preprocessor {
+    /// Creates a new preprocessor without defines nor includes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines `name`, which is replaced by `value` in the shaders.
+    pub fn define(&mut self, name: &str, value: impl ToString) {
+        self.defines.insert(name.to_string(), value.to_string());
+    }
+
+    /// Returns whether `name` is defined.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    /// Registers a source that can be included by the shaders as `name`.
+    pub fn add_include(&mut self, name: &str, source: &str) {
+        self.includes.insert(name.to_string(), source.to_string());
+    }
+
+    /// Preprocesses a shader.
+    ///
+    /// Defines made by the shader itself only apply to the rest of the shader
+    /// and to the files it includes afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: The name of the shader, used in error messages.
+    /// - `source`: The source of the shader.
+    ///
+    /// # Returns
+    ///
+    /// - The preprocessed source, or an error message with the file and line
+    ///   of the faulty directive.
+    pub fn process(&self, name: &str, source: &str) -> Result<String, String> {
+        self.process_mapped(name, source).map(|(output, _)| output)
+    }
+
+    /// Preprocesses a shader like [`Preprocessor::process`], also returning
+    /// where each line of the output comes from.
+    ///
+    /// # Returns
+    ///
+    /// - The preprocessed source and the origin of each of its lines, or an
+    ///   error message with the file and line of the faulty directive.
+    pub fn process_mapped(
+        &self,
+        name: &str,
+        source: &str,
+    ) -> Result<(String, Vec<SourceLine>), String> {
+        let mut defines = self.defines.clone();
+        let mut output = String::with_capacity(source.len());
+        let mut lines = Vec::new();
+        self.process_file(name, source, &mut defines, &mut output, &mut lines, 0)?;
+        Ok((output, lines))
+    }
+
+    fn process_file(
+        &self,
+        name: &str,
+        source: &str,
+        defines: &mut HashMap<String, String>,
+        output: &mut String,
+        lines: &mut Vec<SourceLine>,
+        depth: usize,
+    ) -> Result<(), String> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(format!("{name}: too many nested includes"));
+        }
+
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: &str| format!("{name}:{}: {message}", index + 1);
+            let origin = SourceLine {
+                file: name.to_string(),
+                line: index + 1,
+            };
+            let active = conditionals.last().is_none_or(|c| c.active);
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    output.push_str(&substitute(line, defines));
+                }
+                output.push('\n');
+                lines.push(origin);
+                continue;
+            };
+
+            let mut words = directive.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let argument = words.next();
+            match (keyword, argument) {
+                ("ifdef" | "ifndef", Some(define)) => {
+                    let defined = defines.contains_key(define);
+                    conditionals.push(Conditional {
+                        active: active && (defined == (keyword == "ifdef")),
+                        parent_active: active,
+                        in_else: false,
+                    });
+                }
+                ("else", None) => match conditionals.last_mut() {
+                    Some(c) if !c.in_else => {
+                        c.active = c.parent_active && !c.active;
+                        c.in_else = true;
+                    }
+                    _ => return Err(error("#else without matching #ifdef")),
+                },
+                ("endif", None) => {
+                    if conditionals.pop().is_none() {
+                        return Err(error("#endif without matching #ifdef"));
+                    }
+                }
+                ("define", Some(define)) => {
+                    if active {
+                        let value = words.collect::<Vec<_>>().join(" ");
+                        defines.insert(define.to_string(), value);
+                    }
+                }
+                ("include", Some(include)) => {
+                    if active {
+                        let include = include.trim_matches('"');
+                        let Some(included) = self.includes.get(include) else {
+                            return Err(error(&format!("unknown include \"{include}\"")));
+                        };
+                        self.process_file(include, included, defines, output, lines, depth + 1)?;
+                    }
+                }
+                _ => return Err(error(&format!("invalid directive #{directive}"))),
+            }
+            output.push('\n');
+            lines.push(origin);
+        }
+
+        if !conditionals.is_empty() {
+            return Err(format!("{name}: missing #endif"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the defined identifiers of a line by their value, leaving its
+/// comment untouched.
+fn substitute(line: &str, defines: &HashMap<String, String>) -> String {
+    let (code, comment) = match line.find("//") {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    };
+
+    let mut result = String::with_capacity(line.len());
+    let mut identifier = String::new();
+    for c in code.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            identifier.push(c);
+            continue;
+        }
+        push_identifier(&mut result, &identifier, defines);
+        identifier.clear();
+        result.push(c);
+    }
+    push_identifier(&mut result, &identifier, defines);
+
+    result.push_str(comment);
+    result
+}
+
+/// Pushes an identifier, or its value if it is defined.
+fn push_identifier(result: &mut String, identifier: &str, defines: &HashMap<String, String>) {
+    match defines.get(identifier) {
+        Some(value) => result.push_str(value),
+        None => result.push_str(identifier),
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use super::{bla::BlaTable, perturbation::DeepZoomView};
+
+/// Parameters from which a reference orbit and its BLA table are computed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceParams {
+    /// View whose center is the reference point.
+    pub view: DeepZoomView,
+    /// The maximum number of iterations.
+    pub max_iter: u32,
+    /// The escape radius.
+    pub bailout: f32,
+    /// Whether a BLA table is built for the orbit.
+    pub use_bla: bool,
+}
+
+/// Reference orbit of the perturbation kernel, with its BLA table.
+pub struct Reference {
+    /// Parameters the reference was computed from.
+    pub params: ReferenceParams,
+    /// The orbit, starting at z = 0, rounded to `f32`.
+    pub orbit: Vec<[f32; 2]>,
+    /// The table used to skip iterations, valid for offsets up to the corners
+    /// of the view, if requested.
+    pub bla_table: Option<BlaTable>,
+}
+
+impl Reference {
+    /// Computes the reference orbit at the center of the view, and its BLA
+    /// table if requested.
+    ///
+    /// This is expensive for deep zooms and large iteration counts, as the
+    /// orbit is computed with arbitrary precision.
+    pub fn new(params: ReferenceParams) -> Self {
+        let orbit = params
+            .view
+            .reference_orbit(params.max_iter, params.bailout as f64);
+        let bla_table = params.use_bla.then(|| {
+            let (w, h) = params.view.get_size();
+            BlaTable::new(&orbit, 0.5 * w.hypot(h))
+        });
+        Self {
+            params,
+            orbit,
+            bla_table,
+        }
+    }
+}
+
+/// Builds reference orbits on a background thread, keeping the last one so
+/// that it is only rebuilt when its parameters change.
+///
+/// A single reference is built at a time. Parameters requested meanwhile are
+/// built once it is done, only keeping the latest ones.
+#[derive(Default)]
+pub struct ReferenceCache {
+    /// Parameters of the last reference built or being built.
+    requested: Option<ReferenceParams>,
+    /// Receiver of the reference being built, if any.
+    building: Option<Receiver<Reference>>,
+    /// Parameters waiting for the current build to finish.
+    queued: Option<ReferenceParams>,
+    /// Whether a reference was ever built.
+    built: bool,
+}
+
+impl ReferenceCache {
+    /// Creates a new cache, without any reference.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests a reference for the given parameters, starting to build it
+    /// unless it is already built or being built.
+    pub fn request(&mut self, params: ReferenceParams) {
+        let is_requested = self.requested.as_ref() == Some(&params);
+        if self.building.is_some() {
+            // Going back to the parameters being built cancels the queued ones
+            self.queued = (!is_requested).then_some(params);
+        } else if !is_requested {
+            self.spawn(params);
+        }
+    }
+
+    /// Returns the reference whose build finished since the last call, if any.
+    ///
+    /// A finished reference is returned even if newer parameters were
+    /// requested meanwhile, as it is consistent with its own parameters and
+    /// is closer to them than the previous one. The newer parameters are then
+    /// built in turn.
+    pub fn poll(&mut self) -> Option<Reference> {
+        let reference = match self.building.as_ref()?.try_recv() {
+            Ok(reference) => reference,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                // The build panicked, allow the same parameters to be
+                // requested again
+                eprintln!("Failed to build the reference orbit");
+                self.building = None;
+                self.requested = None;
+                if let Some(params) = self.queued.take() {
+                    self.spawn(params);
+                }
+                return None;
+            }
+        };
+
+        self.building = None;
+        self.built = true;
+        if let Some(params) = self.queued.take() {
+            self.spawn(params);
+        }
+        Some(reference)
+    }
+
+    /// Returns whether a reference was ever built, such that the perturbation
+    /// kernel has an orbit to iterate.
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    /// Returns whether a reference is being built.
+    pub fn is_building(&self) -> bool {
+        self.building.is_some()
+    }
+
+    fn spawn(&mut self, params: ReferenceParams) {
+        let (sender, receiver) = mpsc::channel();
+        self.requested = Some(params.clone());
+        self.building = Some(receiver);
+        thread::spawn(move || {
+            // The cache may have been dropped meanwhile, in which case the
+            // reference is not needed anymore
+            let _ = sender.send(Reference::new(params));
+        });
+    }
+}
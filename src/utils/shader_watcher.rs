@@ -0,0 +1,81 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+/// Directory of the shaders in the source tree, from which they are reloaded.
+pub const SHADER_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/utils/shaders");
+
+/// Watches shader files on disk for changes.
+///
+/// Files are watched by polling their modification time, which is cheap
+/// enough to be done every frame for a handful of files.
+pub struct ShaderWatcher {
+    directory: PathBuf,
+    /// Names of the watched files, with their last seen modification time.
+    files: Vec<(String, Option<SystemTime>)>,
+}
+
+impl ShaderWatcher {
+    /// Creates a new watcher of files in `directory`.
+    ///
+    /// All the files are reported as changed by the first call to
+    /// [`ShaderWatcher::changed_files`], so that their current version on
+    /// disk is loaded.
+    pub fn new(directory: impl Into<PathBuf>, names: &[&str]) -> Self {
+        Self {
+            directory: directory.into(),
+            files: names.iter().map(|name| (name.to_string(), None)).collect(),
+        }
+    }
+
+    /// Returns the files modified since the last call, with their new source.
+    ///
+    /// If a file cannot be read, no file is reported as seen, so that the
+    /// next call reports all the modified files again.
+    ///
+    /// # Returns
+    ///
+    /// - The names of the modified files with their source, or an error
+    ///   message with the path of the first file which could not be read.
+    pub fn changed_files(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut changed = Vec::new();
+        for (index, (name, last_modified)) in self.files.iter().enumerate() {
+            let path = self.directory.join(name);
+            let error = |error: std::io::Error| format!("{}: {error}", path.display());
+
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .map_err(error)?;
+            if *last_modified != Some(modified) {
+                let source = fs::read_to_string(&path).map_err(error)?;
+                changed.push((index, modified, source));
+            }
+        }
+
+        Ok(changed
+            .into_iter()
+            .map(|(index, modified, source)| {
+                let (name, last_modified) = &mut self.files[index];
+                *last_modified = Some(modified);
+                (name.clone(), source)
+            })
+            .collect())
+    }
+}
+
+/// Replaces `current` by a rebuilt version, keeping it if the rebuild fails
+/// so that the last valid shaders are used until the errors are fixed.
+///
+/// # Returns
+///
+/// - The error of the rebuild, if any.
+pub fn rebuild_or_keep<T>(
+    current: &mut T,
+    rebuild: impl FnOnce() -> Result<T, String>,
+) -> Option<String> {
+    match rebuild() {
+        Ok(rebuilt) => {
+            *current = rebuilt;
+            None
+        }
+        Err(error) => Some(error),
+    }
+}
@@ -0,0 +1,30 @@
+// Helpers shared by the compute and post-processing shaders
+
+fn hsv2rgb(h: f32, s: f32, v: f32) -> vec3f {
+    let c = v * s;
+    let hp = fract(h) * 6.0;
+    let x = c * (1.0 - abs(fract(hp) * 2.0 - 1.0));
+    var rgb = vec3f(0.0);
+    if hp < 1.0 {
+        rgb = vec3f(c, x, 0.0);
+    } else if hp < 2.0 {
+        rgb = vec3f(x, c, 0.0);
+    } else if hp < 3.0 {
+        rgb = vec3f(0.0, c, x);
+    } else if hp < 4.0 {
+        rgb = vec3f(0.0, x, c);
+    } else if hp < 5.0 {
+        rgb = vec3f(x, 0.0, c);
+    } else {
+        rgb = vec3f(c, 0.0, x);
+    }
+    let m = v - c;
+    return rgb + vec3<f32>(m);
+}
+
+fn get_luminance(color: vec4<f32>) -> f32 {
+    // Compute a scalar luminance/brightness from RGB
+    // return max(max(color.r, color.g), color.b);
+    // Alternatively, for perceptual luminance:
+    return dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
+}
@@ -0,0 +1,119 @@
+// Complex helpers shared by the kernels of compute.wgsl and the WGSL code
+// generated from user formulas, see formula.rs. Complex numbers are stored as
+// vec2(re, im), in f32 since WGSL has no transcendental functions in f64
+
+fn cx_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn cx_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+fn cx_sqr(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y);
+}
+
+fn cx_conj(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(z.x, -z.y);
+}
+
+// Raises z to an integer power n by repeated squaring
+fn cx_powi(z: vec2<f32>, n: i32) -> vec2<f32> {
+    var base = z;
+    var e = abs(n);
+    var result = vec2<f32>(1.0, 0.0);
+    while e > 0 {
+        if (e & 1) != 0 {
+            result = cx_mul(result, base);
+        }
+        base = cx_mul(base, base);
+        e = e >> 1u;
+    }
+    if n < 0 {
+        return cx_div(vec2<f32>(1.0, 0.0), result);
+    }
+    return result;
+}
+
+fn cx_arg(z: vec2<f32>) -> f32 {
+    return atan2(z.y, z.x);
+}
+
+fn cx_exp(z: vec2<f32>) -> vec2<f32> {
+    return exp(z.x) * vec2<f32>(cos(z.y), sin(z.y));
+}
+
+// Principal branch of the logarithm
+fn cx_log(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(log(length(z)), atan2(z.y, z.x));
+}
+
+// Principal branch of z^w, with 0^w = 0
+fn cx_pow(z: vec2<f32>, w: vec2<f32>) -> vec2<f32> {
+    if dot(z, z) == 0.0 {
+        return vec2<f32>(0.0);
+    }
+    return cx_exp(cx_mul(w, cx_log(z)));
+}
+
+// Raises z to a real power d
+fn cx_powf(z: vec2<f32>, d: f32) -> vec2<f32> {
+    let r2 = dot(z, z);
+    if r2 == 0.0 {
+        return vec2<f32>(0.0);
+    }
+    let r = pow(r2, 0.5 * d);
+    let theta = d * atan2(z.y, z.x);
+    return r * vec2<f32>(cos(theta), sin(theta));
+}
+
+// Principal branch of the square root
+fn cx_sqrt(z: vec2<f32>) -> vec2<f32> {
+    let theta = 0.5 * atan2(z.y, z.x);
+    return sqrt(length(z)) * vec2<f32>(cos(theta), sin(theta));
+}
+
+fn cx_sin(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(sin(z.x) * cosh(z.y), cos(z.x) * sinh(z.y));
+}
+
+fn cx_cos(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(cos(z.x) * cosh(z.y), -sin(z.x) * sinh(z.y));
+}
+
+fn cx_tan(z: vec2<f32>) -> vec2<f32> {
+    return cx_div(cx_sin(z), cx_cos(z));
+}
+
+fn cx_sinh(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(sinh(z.x) * cos(z.y), cosh(z.x) * sin(z.y));
+}
+
+fn cx_cosh(z: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(cosh(z.x) * cos(z.y), sinh(z.x) * sin(z.y));
+}
+
+fn cx_tanh(z: vec2<f32>) -> vec2<f32> {
+    return cx_div(cx_sinh(z), cx_cosh(z));
+}
+
+// Arithmetic in the precision of the kernels, vec2float, which is the f32
+// arithmetic above unless the shader computes in f64
+#ifdef F64
+fn cx_mul_float(a: vec2<f64>, b: vec2<f64>) -> vec2<f64> {
+    return vec2<f64>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn cx_div_float(a: vec2<f64>, b: vec2<f64>) -> vec2<f64> {
+    return vec2<f64>(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+fn cx_sqr_float(z: vec2<f64>) -> vec2<f64> {
+    return vec2<f64>(z.x * z.x - z.y * z.y, f64(2.0) * z.x * z.y);
+}
+#else
+#define cx_mul_float cx_mul
+#define cx_div_float cx_div
+#define cx_sqr_float cx_sqr
+#endif
@@ -1,23 +1,149 @@
-// Aliases for types to quickly change the precision of the shader.
+#include "common.wgsl"
+#include "complex.wgsl"
+#include "formula.wgsl"
+#include "formulas.wgsl"
+
+// Aliases for types to change the precision of the shader, following the
+// `f64` feature.
+#ifdef F64
+alias float = f64;
+alias vec2float = vec2<f64>;
+#else
 alias float = f32;
 alias vec2float = vec2<f32>;
+#endif
 
 struct FaradayData {
     max_iter: u32,
     num_particles: u32,
-    _padding: vec2<u32>,
+    kernel: u32,
+    exponent: f32,
+    bailout: f32,
+    phoenix_p: f32,
+    delta_exponent: i32,
+    reference_len: u32,
+    delta_span: vec2<f32>,
+    bla_levels: u32,
+    double_single: u32,
     dt: float,
     mu: float,
     x_range: vec2float,
     y_range: vec2float,
+    julia_c: vec2float,
+    x_range_lo: vec2<f32>,
+    y_range_lo: vec2<f32>,
+    coloring: u32,
+    thickness: f32,
+    interior_checks: u32,
+    interior_coloring: u32,
+    stripe_frequency: f32,
+    color_density: f32,
+    accumulated_frames: u32,
+    param_a: f32,
+    param_b: f32,
+    param_c: f32,
+    param_d: f32,
+    flow_view: u32,
+    lic_length: u32,
+    lic_coloring: u32,
+    integrator: u32,
+    tolerance: f32,
+    cycle_tolerance: f32,
+    forcing_amplitude: f32,
+    forcing_frequency: f32,
+    section_phase: f32,
+};
+
+struct NewtonData {
+    num_roots: u32,
+    relaxation: f32,
+    roots: array<vec2<f32>, MAX_NEWTON_ROOTS>,
+};
+
+struct LyapunovData {
+    sequence_len: u32,
+    warmup: u32,
+    sequence: array<u32, MAX_LYAPUNOV_SEQUENCE_LEN>,
+};
+
+struct LimitCycleData {
+    period: f32,
+    mu: f32,
+    points: array<vec2<f32>, LIMIT_CYCLE_LEN>,
+};
+
+struct OrbitTrapData {
+    shape: u32,
+    value: u32,
+    center: vec2<f32>,
+    angle: f32,
+    radius: f32,
+};
+
+// Distances from an orbit to the orbit trap, accumulated while iterating
+struct TrapState {
+    min_distance: f32,
+    sum_distance: f32,
+    count: u32,
+    // Iteration at which the orbit first hit the trap, 0 if it never did
+    first_hit: u32,
+    // Color of the image trap where the orbit first hit it
+    hit_color: vec4<f32>,
+};
+
+// Sum of the terms of an orbit averaged by the stripe and triangle inequality
+// colorings, accumulated while iterating
+struct AverageState {
+    sum: f32,
+    // Last term of the sum, removed to interpolate with the previous average
+    last: f32,
+    count: u32,
+};
+
+// Bilinear approximation of a block of iterations of the perturbation
+// formula, mapping dz to A dz + B dc
+struct BlaStep {
+    a: vec2<f32>,
+    b: vec2<f32>,
+    a_exponent: i32,
+    b_exponent: i32,
+    log2_radius: f32,
+    _padding: u32,
 };
 
 @group(0) @binding(0)
 var tex: texture_storage_2d<rgba32float, read_write>;
 @group(0) @binding(1)
 var<uniform> fdata: FaradayData;
+@group(0) @binding(3)
+var<storage, read> ndata: NewtonData;
+@group(0) @binding(4)
+var<storage, read> reference_orbit: array<vec2<f32>>;
+@group(0) @binding(5)
+var<storage, read> bla_table: array<BlaStep>;
+// Number of pixels whose iteration was stopped early by each interior check,
+// indexed by the position of its bit in the mask
+@group(0) @binding(6)
+var<storage, read_write> early_exits: array<atomic<u32>, INTERIOR_CHECK_COUNT>;
+@group(0) @binding(7)
+var<uniform> trap: OrbitTrapData;
+@group(0) @binding(8)
+var<storage, read> lyapunov_data: LyapunovData;
+// Number of points of the Buddhabrot orbits or of the attractors which landed
+// in each pixel, with the red, green and blue channels of each pixel next to
+// each other
+@group(0) @binding(9)
+var<storage, read_write> density: array<atomic<u32>>;
+@group(0) @binding(10)
+var<storage, read> limit_cycle: LimitCycleData;
+@group(0) @binding(11)
+var trap_image: texture_2d<f32>;
 
-@compute @workgroup_size(16, 16)
+// Amplitude and angular frequency of the forcing of the forced oscillators,
+// which the Arnold tongues view sets per pixel
+var<private> forcing: vec2<f32>;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn cs_main(
     @builtin(global_invocation_id) gid: vec3<u32>,
 ) {
@@ -38,18 +164,147 @@ fn cs_main(
     let dx = (fdata.x_range[1] - fdata.x_range[0]) / float(dims.x);
     let dy = (fdata.y_range[1] - fdata.y_range[0]) / float(dims.y);
 
-    let color = mandelbrot(vec2float(x, y));
-    // let color = math_fn(x, y, dx, dy, 3.0);
-    // let color = van_der_pol(vec2<f32>(x, y));
+    // Run the selected kernel
+    forcing = vec2<f32>(fdata.forcing_amplitude, fdata.forcing_frequency);
+    var color: vec4<f32>;
+    if fdata.kernel == KERNEL_JULIA {
+        color = julia(vec2float(x, y), dx);
+    } else if fdata.kernel == KERNEL_DEEP_MANDELBROT {
+        color = mandelbrot_perturbation(vec2<f32>(uv));
+    } else if fdata.kernel == KERNEL_NEWTON {
+        color = newton(vec2float(x, y));
+    } else if fdata.kernel == KERNEL_MATH_FN {
+        color = math_fn(x, y, dx, dy, float(3.0));
+    } else if is_forced_kernel() && fdata.flow_view == FLOW_VIEW_POINCARE_SECTION {
+        color = density_color(gid.xy, dims);
+    } else if is_forced_kernel() && fdata.flow_view == FLOW_VIEW_ARNOLD_TONGUES {
+        color = arnold_tongues(x, y);
+    } else if is_flow_kernel() && fdata.flow_view == FLOW_VIEW_LIC {
+        color = lic(vec2float(x, y), dx, dy);
+    } else if is_flow_kernel() && fdata.flow_view == FLOW_VIEW_BASINS {
+        color = basins(vec2float(x, y));
+    } else if fdata.kernel == KERNEL_VAN_DER_POL && fdata.flow_view == FLOW_VIEW_LIMIT_CYCLE {
+        color = limit_cycle_color(vec2float(x, y));
+    } else if is_flow_kernel() {
+        color = van_der_pol(vec2float(x, y));
+    } else if fdata.kernel == KERNEL_LYAPUNOV {
+        color = lyapunov(x, y);
+    } else if fdata.kernel == KERNEL_BUDDHABROT || fdata.kernel == KERNEL_CLIFFORD
+        || fdata.kernel == KERNEL_DE_JONG || fdata.kernel == KERNEL_SVENSSON
+        || fdata.kernel == KERNEL_LORENZ {
+        color = density_color(gid.xy, dims);
+    } else if fdata.kernel == KERNEL_MANDELBROT && fdata.double_single != 0u
+        && (fdata.coloring == COLORING_BANDED || fdata.coloring == COLORING_SMOOTH) {
+        // The other colorings do not follow the orbit in double-single
+        color = mandelbrot_double_single(vec2<f32>(uv));
+    } else if fdata.kernel == KERNEL_MANDELBROT {
+        color = mandelbrot(vec2float(x, y), dx);
+    } else {
+        let c = vec2float(x, y);
+        color = escape_time(escape_time_initial_z(c), c);
+    }
 
     textureStore(tex, vec2<u32>(gid.xy), color);
 }
 
-fn mandelbrot(z_initial: vec2float) -> vec4<f32> {
-    // Initialize mandelbrot at z = 0
-    var z = vec2float(float(0.0), float(0.0));
+fn mandelbrot(c: vec2float, pixel_size: float) -> vec4<f32> {
+    // Skip the points of the main cardioid and the period-2 bulb, which
+    // would run all the iterations
+    let check = interior_check(c);
+    if check != 0u {
+        return known_cycle_color(vec2<f32>(c), check);
+    }
+
+    // Initialize mandelbrot at z = 0, whose derivative dz/dc is 0
+    let zero = vec2float(float(0.0), float(0.0));
+    return quadratic_escape(zero, c, zero, pixel_size);
+}
+
+// Colors a point c found by an interior check, counting it as an early exit
+fn known_cycle_color(c: vec2<f32>, check: u32) -> vec4<f32> {
+    atomicAdd(&early_exits[countTrailingZeros(check)], 1u);
+
+    // Their attracting cycles are known in closed form, from the roots
+    // of z^2 - z + c for the fixed point of the cardioid, and of
+    // z^2 + z + c + 1 for the 2-cycle of the bulb
+    if check == INTERIOR_CARDIOID {
+        let root = cx_sqrt(vec2<f32>(1.0, 0.0) - 4.0 * c);
+        let z = 0.5 * (vec2<f32>(1.0, 0.0) - root);
+        return interior_color(z, 1u, 2.0 * length(z));
+    }
+    let root = cx_sqrt(vec2<f32>(-3.0, 0.0) - 4.0 * c);
+    let z = 0.5 * (vec2<f32>(-1.0, 0.0) + root);
+    return interior_color(z, 2u, 4.0 * length(c + vec2<f32>(1.0, 0.0)));
+}
+
+// Returns the interior check which finds c in the main cardioid or the
+// period-2 bulb of the Mandelbrot set, or 0 if none does
+fn interior_check(c: vec2float) -> u32 {
+    let y2 = c.y * c.y;
+
+    if (fdata.interior_checks & INTERIOR_CARDIOID) != 0u {
+        let x = c.x - float(0.25);
+        let q = x * x + y2;
+        if q * (q + x) <= float(0.25) * y2 {
+            return INTERIOR_CARDIOID;
+        }
+    }
+
+    if (fdata.interior_checks & INTERIOR_BULB) != 0u {
+        let x = c.x + float(1.0);
+        if x * x + y2 <= float(0.0625) {
+            return INTERIOR_BULB;
+        }
+    }
+
+    return 0u;
+}
+
+fn julia(z_initial: vec2float, pixel_size: float) -> vec4<f32> {
+    // The constant c is the seed of the Julia set, and the derivative is
+    // taken with respect to z_initial
+    let one = vec2float(float(1.0), float(0.0));
+    return quadratic_escape(z_initial, fdata.julia_c, one, pixel_size);
+}
+
+// Iterates z -> z^2 + c starting from z_initial and colors by escape time.
+//
+// For distance estimation, the derivative dz of the orbit is tracked from
+// dz_initial, with respect to c if it is 0 and to z_initial if it is 1.
+fn quadratic_escape(
+    z_initial: vec2float,
+    c: vec2float,
+    dz_initial: vec2float,
+    pixel_size: float,
+) -> vec4<f32> {
+    let track_derivative = fdata.coloring == COLORING_DISTANCE;
+    let track_trap = fdata.coloring == COLORING_ORBIT_TRAP;
+    var trap_state = trap_start();
+    let track_average = fdata.coloring == COLORING_STRIPE
+        || fdata.coloring == COLORING_TRIANGLE_INEQUALITY;
+    var average_state = AverageState(0.0, 0.0, 0u);
+    // The derivative with respect to c gains 1 at each iteration
+    let dc = vec2float(float(1.0), float(0.0)) - dz_initial;
+    var z = z_initial;
+    var dz = dz_initial;
     var iter = 0u;
 
+    // Brent's cycle detection compares z to a point saved at increasing
+    // powers of two, with the derivative of z with respect to that point.
+    // Coloring the interior by its cycle needs the detection even when it
+    // does not stop the iteration.
+    let stop_periodic = (fdata.interior_checks & INTERIOR_PERIODICITY) != 0u;
+    var detect_cycle = stop_periodic
+        || fdata.interior_coloring == INTERIOR_COLORING_PERIOD
+        || fdata.interior_coloring == INTERIOR_COLORING_MULTIPLIER;
+    let tolerance = float(PERIODICITY_TOLERANCE) * pixel_size;
+    var z_saved = z;
+    var dz_saved = vec2float(float(1.0), float(0.0));
+    var period = 0u;
+    var period_limit = 1u;
+    var cycle_period = 0u;
+    var cycle_multiplier = 0.0;
+
     loop {
         if iter >= fdata.max_iter {
             break;
@@ -58,16 +313,599 @@ fn mandelbrot(z_initial: vec2float) -> vec4<f32> {
         let z2 = z * z;
 
         // Check for divergence
-        // We assume divergence if the modulus of z is greater than 4.0
-        if z2[0] + z2[1] > float(4.0) {
+        // We assume divergence if the modulus of z is greater than the bailout
+        if z2[0] + z2[1] > float(fdata.bailout * fdata.bailout) {
             break;
         }
 
-        // Compute next iteration
-        z = vec2float(z2[0] - z2[1] + z_initial[0], float(2.0) * z[0] * z[1] + z_initial[1]);
+        // Compute next iteration, dz = 2 z dz + dc before updating z
+        if track_derivative {
+            dz = float(2.0) * cx_mul_float(z, dz) + dc;
+        }
+        if detect_cycle {
+            dz_saved = float(2.0) * cx_mul_float(z, dz_saved);
+        }
+        z = vec2float(z2[0] - z2[1] + c[0], float(2.0) * z[0] * z[1] + c[1]);
         iter = iter + 1u;
+        if track_trap {
+            trap_update(&trap_state, vec2<f32>(z), iter);
+        }
+        if track_average {
+            average_update(&average_state, vec2<f32>(z), f32(z2[0] + z2[1]), vec2<f32>(c));
+        }
+
+        // Only attracting cycles stop the iteration, as points of the
+        // boundary can land exactly on a repelling cycle and still escape.
+        // This mirrors `mandelbrot_iterations` in interior.rs.
+        if detect_cycle {
+            let d = z - z_saved;
+            if dot(d, d) < tolerance * tolerance && dot(dz_saved, dz_saved) < float(1.0) {
+                cycle_period = period + 1u;
+                cycle_multiplier = length(vec2<f32>(dz_saved));
+                if stop_periodic {
+                    atomicAdd(&early_exits[countTrailingZeros(INTERIOR_PERIODICITY)], 1u);
+                    iter = fdata.max_iter;
+                    break;
+                }
+                detect_cycle = false;
+            }
+            period = period + 1u;
+            if period == period_limit {
+                z_saved = z;
+                dz_saved = vec2float(float(1.0), float(0.0));
+                period = 0u;
+                period_limit = period_limit * 2u;
+            }
+        }
+    }
+
+    if iter >= fdata.max_iter {
+        return interior_color(vec2<f32>(z), cycle_period, cycle_multiplier);
+    }
+    if track_derivative {
+        return distance_color(iter, vec2<f32>(z), vec2<f32>(dz), f32(pixel_size));
+    }
+    if track_trap {
+        return trap_color(trap_state, escape_color(iter, vec2<f32>(z), 2.0));
+    }
+    if track_average {
+        return average_color(average_state, iter, vec2<f32>(z));
+    }
+    return escape_color(iter, vec2<f32>(z), 2.0);
+}
+
+// Renders the Mandelbrot set with double-single arithmetic, using the low
+// parts of the ranges to roughly double the precision of f32
+fn mandelbrot_double_single(uv: vec2<f32>) -> vec4<f32> {
+    let x0 = vec2<f32>(f32(fdata.x_range[0]), fdata.x_range_lo[0]);
+    let x1 = vec2<f32>(f32(fdata.x_range[1]), fdata.x_range_lo[1]);
+    let y0 = vec2<f32>(f32(fdata.y_range[0]), fdata.y_range_lo[0]);
+    let y1 = vec2<f32>(f32(fdata.y_range[1]), fdata.y_range_lo[1]);
+
+    // Get x/y in "math" space
+    let cx = ds_add(x0, ds_mul(ds_sub(x1, x0), vec2<f32>(uv.x, 0.0)));
+    let cy = ds_add(y0, ds_mul(ds_sub(y1, y0), vec2<f32>(uv.y, 0.0)));
+
+    // The interior checks only need the high parts of c
+    let check = interior_check(vec2float(vec2<f32>(cx.x, cy.x)));
+    if check != 0u {
+        return known_cycle_color(vec2<f32>(cx.x, cy.x), check);
+    }
+
+    var zx = vec2<f32>(0.0);
+    var zy = vec2<f32>(0.0);
+    var iter = 0u;
+
+    // Brent's cycle detection, as in quadratic_escape. The distance to the
+    // saved point is taken in double-single, as the tolerance is below the
+    // precision of the high parts, while the derivative only needs them.
+    let stop_periodic = (fdata.interior_checks & INTERIOR_PERIODICITY) != 0u;
+    var detect_cycle = stop_periodic
+        || fdata.interior_coloring == INTERIOR_COLORING_PERIOD
+        || fdata.interior_coloring == INTERIOR_COLORING_MULTIPLIER;
+    let pixel_size = ds_sub(x1, x0).x / f32(textureDimensions(tex).x);
+    let tolerance = f32(PERIODICITY_TOLERANCE) * pixel_size;
+    var zx_saved = zx;
+    var zy_saved = zy;
+    var dz_saved = vec2<f32>(1.0, 0.0);
+    var period = 0u;
+    var period_limit = 1u;
+    var cycle_period = 0u;
+    var cycle_multiplier = 0.0;
+
+    loop {
+        if iter >= fdata.max_iter {
+            break;
+        }
+
+        let zx2 = ds_mul(zx, zx);
+        let zy2 = ds_mul(zy, zy);
+
+        // Check for divergence, the high parts are precise enough for it
+        if zx2.x + zy2.x > fdata.bailout * fdata.bailout {
+            break;
+        }
+
+        // Compute next iteration, dz = 2 z dz before updating z
+        if detect_cycle {
+            dz_saved = 2.0 * cx_mul(vec2<f32>(zx.x, zy.x), dz_saved);
+        }
+        let zxy = ds_mul(zx, zy);
+        zx = ds_add(ds_sub(zx2, zy2), cx);
+        zy = ds_add(ds_add(zxy, zxy), cy);
+        iter = iter + 1u;
+
+        if detect_cycle {
+            let d = vec2<f32>(ds_sub(zx, zx_saved).x, ds_sub(zy, zy_saved).x);
+            if dot(d, d) < tolerance * tolerance && dot(dz_saved, dz_saved) < 1.0 {
+                cycle_period = period + 1u;
+                cycle_multiplier = length(dz_saved);
+                if stop_periodic {
+                    atomicAdd(&early_exits[countTrailingZeros(INTERIOR_PERIODICITY)], 1u);
+                    iter = fdata.max_iter;
+                    break;
+                }
+                detect_cycle = false;
+            }
+            period = period + 1u;
+            if period == period_limit {
+                zx_saved = zx;
+                zy_saved = zy;
+                dz_saved = vec2<f32>(1.0, 0.0);
+                period = 0u;
+                period_limit = period_limit * 2u;
+            }
+        }
+    }
+
+    if iter >= fdata.max_iter {
+        return interior_color(vec2<f32>(zx.x, zy.x), cycle_period, cycle_multiplier);
+    }
+    return escape_color(iter, vec2<f32>(zx.x, zy.x), 2.0);
+}
+
+// Renders the Mandelbrot set with perturbation theory around the reference
+// orbit computed on the CPU at the center of the view.
+//
+// The delta dz between the orbit of the pixel and the reference orbit is
+// stored as w * 2^k, with w kept close to 1, so that f32 can represent deltas
+// far below its smallest normal value.
+fn mandelbrot_perturbation(uv: vec2<f32>) -> vec4<f32> {
+    // Offset of the pixel from the center of the view, as dc * 2^dc_exponent
+    let dc = (uv - vec2<f32>(0.5)) * fdata.delta_span;
+    let dc_exponent = fdata.delta_exponent;
+
+    let reference_len = min(fdata.reference_len, arrayLength(&reference_orbit));
+    let bailout2 = fdata.bailout * fdata.bailout;
+
+    var w = vec2<f32>(0.0);
+    var k = dc_exponent;
+    var m = 0u; // Index in the reference orbit
+    var iter = 0u;
+    var z = vec2<f32>(0.0);
+
+    loop {
+        if iter >= fdata.max_iter {
+            break;
+        }
+
+        // Check for divergence of z = Z + dz, where dz underflows to 0 once
+        // it is negligible next to Z
+        var z_ref = reference_orbit[m];
+        z = z_ref + scale_exp2(w, k);
+        if dot(z, z) > bailout2 {
+            break;
+        }
+
+        // Glitch detection: once |Z + dz| < |dz|, the delta dominates the
+        // reference and loses precision. This requires |Z| < 2 |dz|, which is
+        // checked in log2 space since neither may fit in f32, and then both
+        // are at the scale of w.
+        let log2_dz = log2_length(w) + f32(k);
+        let z_near = log2_length(z_ref) < log2_dz + 1.0;
+        var z_scaled = w;
+        if z_near {
+            z_scaled = scale_exp2(z_ref, -k) + w;
+        }
+        let glitch = z_near && dot(z_scaled, z_scaled) < dot(w, w);
+
+        // Rebase the pixel onto the start of the reference orbit, where Z = 0,
+        // which is also needed when reaching its end. The delta becomes
+        // Z + dz, which is z itself unless both are at the scale of w.
+        if glitch || m + 1u >= reference_len {
+            if z_near {
+                w = z_scaled;
+            } else {
+                w = z;
+                k = 0;
+            }
+            renormalize(&w, &k);
+            m = 0u;
+            z_ref = reference_orbit[0];
+        }
+
+        // Skip as many iterations as the BLA table allows
+        let lookup = bla_lookup(m, length(w), k, reference_len, fdata.max_iter - iter);
+        let skip = lookup.y;
+        if skip > 0u {
+            // dz = A dz + B dc, with the largest of both terms used as scale
+            let step = bla_table[lookup.x];
+            let ea = k + step.a_exponent;
+            let eb = dc_exponent + step.b_exponent;
+            let e = max(ea, eb);
+            let aw = vec2<f32>(step.a.x * w.x - step.a.y * w.y, step.a.x * w.y + step.a.y * w.x);
+            let bdc = vec2<f32>(step.b.x * dc.x - step.b.y * dc.y, step.b.x * dc.y + step.b.y * dc.x);
+            w = aw * exp2i(ea - e) + bdc * exp2i(eb - e);
+            k = e;
+            m = m + skip;
+            iter = iter + skip;
+            continue;
+        }
+
+        // Compute next iteration: dz = 2 Z dz + dz^2 + dc, with the largest
+        // term used as scale as any of them can dominate when Z is close to 0
+        let zw = 2.0 * vec2<f32>(z_ref.x * w.x - z_ref.y * w.y, z_ref.x * w.y + z_ref.y * w.x);
+        let ww = vec2<f32>(w.x * w.x - w.y * w.y, 2.0 * w.x * w.y);
+        let e = max(max(k + vec_exponent(zw), 2 * k + vec_exponent(ww)), dc_exponent + vec_exponent(dc));
+        w = scale_exp2(zw, k - e) + scale_exp2(ww, 2 * k - e) + scale_exp2(dc, dc_exponent - e);
+        k = e;
+        m = m + 1u;
+        iter = iter + 1u;
+
+        // Renormalize w to keep it close to 1
+        renormalize(&w, &k);
+    }
+
+    return escape_color(iter, z, 2.0);
+}
+
+// Finds the longest step of the BLA table starting at index m of the
+// reference orbit that is valid for |dz| = w_norm * 2^k. Returns the index of
+// the step in the table and the number of iterations it skips, which is 0 if
+// no step is valid.
+fn bla_lookup(m: u32, w_norm: f32, k: i32, reference_len: u32, max_skip: u32) -> vec2<u32> {
+    if m == 0u || reference_len < 3u {
+        return vec2<u32>(0u);
     }
 
+    let log2_dz = log2(max(w_norm, 1e-30)) + f32(k);
+    var found = 0u;
+    var skip = 0u;
+    var offset = 0u;
+    var count = reference_len - 2u;
+    var index = m - 1u;
+    var level = 0u;
+    loop {
+        if level >= fdata.bla_levels || index >= count || (1u << level) > max_skip {
+            break;
+        }
+
+        if log2_dz >= bla_table[offset + index].log2_radius {
+            break;
+        }
+        found = offset + index;
+        skip = 1u << level;
+
+        // A longer step only starts at every other step of this level
+        if (index & 1u) != 0u {
+            break;
+        }
+        offset = offset + count;
+        count = count / 2u;
+        index = index / 2u;
+        level = level + 1u;
+    }
+
+    return vec2<u32>(found, skip);
+}
+
+// Iterates the escape-time formula of the selected kernel starting from
+// z_initial and colors by escape time
+fn escape_time(z_initial: vec2float, c: vec2float) -> vec4<f32> {
+    let track_trap = fdata.coloring == COLORING_ORBIT_TRAP;
+    var trap_state = trap_start();
+    var z = z_initial;
+    var z_prev = vec2float(float(0.0), float(0.0));
+    var iter = 0u;
+
+    loop {
+        if iter >= fdata.max_iter {
+            break;
+        }
+
+        if has_escaped(z) {
+            break;
+        }
+
+        // Compute next iteration, keeping the previous one for Phoenix
+        let z_next = escape_time_step(z, z_prev, c);
+        z_prev = z;
+        z = z_next;
+        iter = iter + 1u;
+        if track_trap {
+            trap_update(&trap_state, vec2<f32>(z), iter);
+        }
+    }
+
+    let color = escape_color(iter, vec2<f32>(z), escape_degree(vec2<f32>(z), vec2<f32>(z_prev)));
+    if track_trap && iter < fdata.max_iter {
+        return trap_color(trap_state, color);
+    }
+    return color;
+}
+
+// Returns the first iterate of the selected escape-time formula
+fn escape_time_initial_z(c: vec2float) -> vec2float {
+    // The critical point of c * sin(z) is pi / 2, whose image is c
+    if fdata.kernel == KERNEL_SINE {
+        return c;
+    }
+    // User formulas may have a fixed point at 0, such as c * z^2
+    if fdata.kernel == KERNEL_FORMULA {
+        return c;
+    }
+    return vec2float(float(0.0), float(0.0));
+}
+
+// Computes one iteration of the selected escape-time formula
+fn escape_time_step(z: vec2float, z_prev: vec2float, c: vec2float) -> vec2float {
+    let one = vec2float(float(1.0), float(0.0));
+    let two = vec2float(float(2.0), float(0.0));
+    let three = float(3.0);
+
+    if fdata.kernel == KERNEL_MULTIBROT {
+        // z^d + c, the power is evaluated in f32
+        return vec2float(cx_powf(vec2<f32>(z), fdata.exponent)) + c;
+    } else if fdata.kernel == KERNEL_BURNING_SHIP {
+        // (|Re(z)| + i |Im(z)|)^2 + c
+        return cx_sqr_float(abs(z)) + c;
+    } else if fdata.kernel == KERNEL_TRICORN {
+        // conj(z)^2 + c
+        return cx_sqr_float(vec2float(z.x, -z.y)) + c;
+    } else if fdata.kernel == KERNEL_MAGNET_I {
+        // ((z^2 + c - 1) / (2z + c - 2))^2
+        return cx_sqr_float(cx_div_float(cx_sqr_float(z) + c - one, float(2.0) * z + c - two));
+    } else if fdata.kernel == KERNEL_MAGNET_II {
+        // ((z^3 + 3(c - 1)z + (c - 1)(c - 2)) / (3z^2 + 3(c - 2)z + (c - 1)(c - 2) + 1))^2
+        let c1 = c - one;
+        let c2 = c - two;
+        let c12 = cx_mul_float(c1, c2);
+        let z2 = cx_sqr_float(z);
+        let num = cx_mul_float(z2, z) + three * cx_mul_float(c1, z) + c12;
+        let den = three * z2 + three * cx_mul_float(c2, z) + c12 + one;
+        return cx_sqr_float(cx_div_float(num, den));
+    } else if fdata.kernel == KERNEL_PHOENIX {
+        // z^2 + c + p * z_prev
+        return cx_sqr_float(z) + c + float(fdata.phoenix_p) * z_prev;
+    } else if fdata.kernel == KERNEL_SINE {
+        // c * sin(z), the sine is evaluated in f32
+        return cx_mul_float(c, vec2float(cx_sin(vec2<f32>(z))));
+    } else if fdata.kernel == KERNEL_EXP {
+        // c * exp(z), the exponential is evaluated in f32
+        return cx_mul_float(c, vec2float(cx_exp(vec2<f32>(z))));
+    } else if fdata.kernel == KERNEL_FORMULA {
+        // User formula, generated in f32
+        return vec2float(formula_escape_time(vec2<f32>(z), vec2<f32>(c)));
+    }
+
+    // z^2 + c
+    return cx_sqr_float(z) + c;
+}
+
+// Checks whether the orbit of the selected escape-time formula has escaped
+fn has_escaped(z: vec2float) -> bool {
+    let bailout = float(fdata.bailout);
+
+    // sin(z) grows exponentially with the imaginary part of z
+    if fdata.kernel == KERNEL_SINE {
+        return abs(z.y) > bailout;
+    }
+
+    // exp(z) grows exponentially with the real part of z
+    if fdata.kernel == KERNEL_EXP {
+        return z.x > bailout;
+    }
+
+    // The magnet formulas also stop when converging to the fixed point 1
+    if fdata.kernel == KERNEL_MAGNET_I || fdata.kernel == KERNEL_MAGNET_II {
+        let d = z - vec2float(float(1.0), float(0.0));
+        if dot(d, d) < float(1e-6) {
+            return true;
+        }
+    }
+
+    return dot(z, z) > bailout * bailout;
+}
+
+// Returns the degree of the selected escape-time formula, which tells how
+// fast log|z| grows once the orbit escapes
+fn escape_degree(z: vec2<f32>, z_prev: vec2<f32>) -> f32 {
+    if fdata.kernel == KERNEL_MULTIBROT {
+        return fdata.exponent;
+    }
+
+    // The growth of transcendental and user formulas is estimated from the
+    // last iteration
+    if fdata.kernel == KERNEL_SINE || fdata.kernel == KERNEL_EXP || fdata.kernel == KERNEL_FORMULA {
+        let log_prev = log_length(z_prev);
+        if log_prev > 0.0 {
+            return max(log_length(z) / log_prev, 1.01);
+        }
+    }
+
+    return 2.0;
+}
+
+// Returns the continuous iteration count of an orbit which escaped at z after
+// iter iterations of a formula of the given degree. It decreases from iter to
+// iter - 1 as |z| grows from the bailout radius to its power degree.
+fn smooth_iter(iter: u32, z: vec2<f32>, degree: f32) -> f32 {
+    let log_z = log_length(z);
+    let log_bailout = log(fdata.bailout);
+
+    // Orbits stopped by another criterion, such as the convergence of the
+    // magnet formulas, keep their integer count
+    if log_z <= log_bailout {
+        return f32(iter);
+    }
+
+    return f32(iter) - clamp(log(log_z / log_bailout) / log(degree), 0.0, 1.0);
+}
+
+// Colors a pixel of the exterior by its estimated distance to the boundary of
+// the set, in pixels, given the derivative dz of its orbit which escaped at z.
+// The boundary is drawn in black with a thickness of fdata.thickness pixels.
+fn distance_color(iter: u32, z: vec2<f32>, dz: vec2<f32>, pixel_size: f32) -> vec4<f32> {
+    let color = escape_color(iter, z, 2.0);
+    if iter >= fdata.max_iter {
+        return color;
+    }
+
+    // d = |z| log|z| / (2 |dz|), computed with logarithms to avoid overflows
+    let log_z = log_length(z);
+    let distance = exp(log_z + log(log_z) - log_length(dz)) * 0.5;
+
+    // Darken the pixels closer to the boundary than the thickness, like the
+    // thickness of math_fn, which is measured on both sides of the curve
+    let shade = clamp(2.0 * distance / (pixel_size * fdata.thickness), 0.0, 1.0);
+    return vec4<f32>(color.rgb * shade, 1.0);
+}
+
+// Returns log|z| without overflowing for large z
+fn log_length(z: vec2<f32>) -> f32 {
+    let a = abs(z);
+    let m = max(a.x, a.y);
+    let r = min(a.x, a.y) / m;
+    return log(m) + 0.5 * log(1.0 + r * r);
+}
+
+// Returns the state of an orbit which has not met the orbit trap yet
+fn trap_start() -> TrapState {
+    return TrapState(3.4028235e38, 0.0, 0u, 0u, vec4<f32>(0.0));
+}
+
+// Accumulates the distance from the point z of an orbit, reached after iter
+// iterations, to the orbit trap
+fn trap_update(state: ptr<function, TrapState>, z: vec2<f32>, iter: u32) {
+    let p = z - trap.center;
+
+    if trap.shape == TRAP_IMAGE {
+        // The image covers a square of side 2 radius centered on the trap,
+        // with its rows from top to bottom
+        let uv = vec2<f32>(p.x, -p.y) / (2.0 * trap.radius) + 0.5;
+        if (*state).first_hit != 0u || any(uv < vec2<f32>(0.0)) || any(uv >= vec2<f32>(1.0)) {
+            return;
+        }
+        let dims = textureDimensions(trap_image);
+        let texel = textureLoad(trap_image, vec2<u32>(uv * vec2<f32>(dims)), 0);
+        if texel.a > 0.5 {
+            (*state).first_hit = iter;
+            (*state).hit_color = texel;
+        }
+        return;
+    }
+
+    // Distances along and across the direction of the trap
+    let direction = vec2<f32>(cos(trap.angle), sin(trap.angle));
+    let along = abs(dot(p, direction));
+    let across = abs(p.x * direction.y - p.y * direction.x);
+
+    var distance: f32;
+    if trap.shape == TRAP_POINT {
+        distance = length(p);
+    } else if trap.shape == TRAP_LINE {
+        distance = across;
+    } else {
+        distance = min(along, across);
+    }
+
+    (*state).min_distance = min((*state).min_distance, distance);
+    (*state).sum_distance = (*state).sum_distance + distance;
+    (*state).count = (*state).count + 1u;
+    if (*state).first_hit == 0u && distance < trap.radius {
+        (*state).first_hit = iter;
+    }
+}
+
+// Colors a pixel by the distances of its orbit to the orbit trap, following
+// the trap value. Orbits which never hit the trap keep the fallback color when
+// the value needs a hit.
+fn trap_color(state: TrapState, fallback: vec4<f32>) -> vec4<f32> {
+    if trap.shape == TRAP_IMAGE {
+        if state.first_hit == 0u {
+            return fallback;
+        }
+        return vec4<f32>(state.hit_color.rgb, 1.0);
+    }
+
+    // Distances are measured in radii of the trap
+    var h: f32;
+    if trap.value == TRAP_MIN_DISTANCE {
+        h = state.min_distance / trap.radius;
+    } else if trap.value == TRAP_AVERAGE_DISTANCE {
+        h = state.sum_distance / (f32(max(state.count, 1u)) * trap.radius);
+    } else {
+        if state.first_hit == 0u {
+            return fallback;
+        }
+        h = f32(state.first_hit) / f32(fdata.max_iter);
+    }
+    return vec4<f32>(hsv2rgb(h, 1.0, 1.0), 1.0);
+}
+
+// Adds the term of the point z of a quadratic orbit to the average of the
+// stripe or triangle inequality coloring, given |z_prev|^2 for the previous
+// point of the orbit and the constant c
+fn average_update(
+    state: ptr<function, AverageState>,
+    z: vec2<f32>,
+    z_prev_norm2: f32,
+    c: vec2<f32>,
+) {
+    var term: f32;
+    if fdata.coloring == COLORING_STRIPE {
+        term = 0.5 * sin(fdata.stripe_frequency * atan2(z.y, z.x)) + 0.5;
+    } else {
+        // |z| lies between ||z_prev|^2 - |c|| and |z_prev|^2 + |c|, which
+        // are equal for the first point of the Mandelbrot orbit
+        let c_norm = length(c);
+        let low = abs(z_prev_norm2 - c_norm);
+        let high = z_prev_norm2 + c_norm;
+        if high - low <= 0.0 {
+            return;
+        }
+        term = (length(z) - low) / (high - low);
+    }
+
+    (*state).sum = (*state).sum + term;
+    (*state).last = term;
+    (*state).count = (*state).count + 1u;
+}
+
+// Colors a pixel of the exterior by the average of the terms of its orbit,
+// which escaped at z after iter iterations.
+//
+// The last term is only partly counted, following the fraction of the
+// continuous iteration count, so that the colors are continuous across the
+// bands of the iteration count.
+fn average_color(state: AverageState, iter: u32, z: vec2<f32>) -> vec4<f32> {
+    if state.count == 0u {
+        return escape_color(iter, z, 2.0);
+    }
+
+    let average = state.sum / f32(state.count);
+    var previous = average;
+    if state.count > 1u {
+        previous = (state.sum - state.last) / f32(state.count - 1u);
+    }
+    let fraction = f32(iter) - smooth_iter(iter, z, 2.0);
+    let value = mix(average, previous, fraction);
+
+    return vec4<f32>(hsv2rgb(value * fdata.color_density, 1.0, 1.0), 1.0);
+}
+
+// Colors a pixel based on the iteration count at which its orbit escaped at z
+fn escape_color(iter: u32, z: vec2<f32>, degree: f32) -> vec4<f32> {
     // Color (BW) based on iteration count
     // var shade: f32;
     // if iter == fdata.max_iter {
@@ -79,22 +917,343 @@ fn mandelbrot(z_initial: vec2float) -> vec4<f32> {
     // return vec4<f32>(shade, shade, shade, 1.0);
 
 
+    // Count iterations continuously for smooth coloring
+    var count = f32(iter);
+    if fdata.coloring == COLORING_SMOOTH && iter < fdata.max_iter {
+        count = smooth_iter(iter, z, degree);
+    }
+
+    // The cycle of the orbit is unknown to the generic kernels
+    if iter == fdata.max_iter {
+        return interior_color(z, 0u, 0.0);
+    }
+
     // Color (RGB) based on iteration count
-    let h = f32(iter) / f32(fdata.max_iter);
+    let h = count / f32(fdata.max_iter);
     let s = 1.0;
-    var v: f32;
-    if iter == fdata.max_iter {
-        v = 0.0;
+    let v = 1.0;
+    return vec4<f32>(hsv2rgb(h, s, v), 1.0);
+}
+
+// Colors a pixel of the interior, whose orbit stopped at z, following the
+// interior coloring mode.
+//
+// The period and multiplier are those of the attracting cycle of the orbit,
+// with a period of 0 if no cycle was detected, which leaves the pixel black.
+// The interior is darker than the exterior so that the boundary of the set
+// stays visible through the post-processing.
+fn interior_color(z: vec2<f32>, period: u32, multiplier: f32) -> vec4<f32> {
+    var h: f32;
+    if fdata.interior_coloring == INTERIOR_COLORING_PERIOD && period != 0u {
+        // Spread consecutive periods around the hue circle with the golden
+        // ratio, so that neighboring components differ
+        h = f32(period) * 0.618034;
+    } else if fdata.interior_coloring == INTERIOR_COLORING_MULTIPLIER && period != 0u {
+        h = multiplier;
+    } else if fdata.interior_coloring == INTERIOR_COLORING_ANGLE {
+        h = atan2(z.y, z.x) / 6.2831855 + 0.5;
     } else {
-        // Normalize the iteration count to [0.0, 1.0]
-        v = 1.0;
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    return vec4<f32>(hsv2rgb(h, 1.0, 0.5), 1.0);
+}
+
+// Accumulates random samples into the density of the Buddhabrot, of the
+// selected attractor or of the Poincaré section of the selected forced
+// oscillator, one sample per invocation.
+@compute @workgroup_size(WORKGROUP_INVOCATIONS)
+fn cs_density(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+) {
+    if gid.x >= fdata.num_particles { return; }
+
+    // The samples depend on the seed and the number of accumulated frames
+    // only, so that renders are reproducible
+    var state = pcg_hash(gid.x ^ pcg_hash(fdata.accumulated_frames ^ BUDDHABROT_SEED));
+    forcing = vec2<f32>(fdata.forcing_amplitude, fdata.forcing_frequency);
+
+    if fdata.kernel == KERNEL_BUDDHABROT {
+        buddhabrot_sample(&state);
+    } else if is_forced_kernel() {
+        // The forced oscillators only accumulate a density in their Poincaré
+        // section view
+        poincare_sample(&state);
+    } else if fdata.kernel == KERNEL_LORENZ {
+        lorenz_sample(&state);
+    } else {
+        attractor_sample(&state);
+    }
+}
+
+// Iterates a random point c of the Mandelbrot set and accumulates the points
+// of its orbit into the density of the Buddhabrot if it escapes.
+//
+// An orbit counts in the red, green and blue channels if it escapes within
+// max_iter, max_iter / 10 and max_iter / 100 iterations respectively, which
+// renders the Nebulabrot.
+fn buddhabrot_sample(state: ptr<function, u32>) {
+    // Sample c in the disk of radius 2 containing the set, skipping the
+    // points which never escape
+    let c = vec2float(
+        float(4.0 * random(state) - 2.0),
+        float(4.0 * random(state) - 2.0),
+    );
+    if dot(c, c) > float(4.0) || interior_check(c) != 0u {
+        return;
+    }
+
+    // Find the escape time of the orbit
+    var z = vec2float(float(0.0), float(0.0));
+    var iter = 0u;
+    loop {
+        if iter >= fdata.max_iter || dot(z, z) > float(4.0) {
+            break;
+        }
+        z = cx_sqr_float(z) + c;
+        iter = iter + 1u;
+    }
+    if iter >= fdata.max_iter {
+        return;
+    }
+    let channels = vec3<bool>(true, iter < fdata.max_iter / 10u, iter < fdata.max_iter / 100u);
+
+    // Iterate the orbit again, adding each of its points to the density
+    z = vec2float(float(0.0), float(0.0));
+    for (var n = 0u; n < iter; n = n + 1u) {
+        z = cx_sqr_float(z) + c;
+        splat(z, channels);
+    }
+}
+
+// Follows the orbit of a random point under the selected 2D attractor map,
+// adding max_iter of its points to the density once it settled on the
+// attractor.
+//
+// The maps use the sines of f32, which WGSL does not define for f64.
+fn attractor_sample(state: ptr<function, u32>) {
+    let a = fdata.param_a;
+    let b = fdata.param_b;
+    let c = fdata.param_c;
+    let d = fdata.param_d;
+
+    var p = vec2<f32>(2.0 * random(state) - 1.0, 2.0 * random(state) - 1.0);
+    let warmup = fdata.max_iter / 10u;
+    for (var n = 0u; n < warmup + fdata.max_iter; n = n + 1u) {
+        if fdata.kernel == KERNEL_CLIFFORD {
+            p = vec2<f32>(
+                sin(a * p.y) + c * cos(a * p.x),
+                sin(b * p.x) + d * cos(b * p.y),
+            );
+        } else if fdata.kernel == KERNEL_DE_JONG {
+            p = vec2<f32>(
+                sin(a * p.y) - cos(b * p.x),
+                sin(c * p.x) - cos(d * p.y),
+            );
+        } else {
+            p = vec2<f32>(
+                d * sin(a * p.x) - sin(b * p.y),
+                c * cos(a * p.x) + cos(b * p.y),
+            );
+        }
+
+        if n >= warmup {
+            splat(vec2float(p), vec3<bool>(true));
+        }
     }
-    return vec4<f32>(hsv2rgb(h, s, v), 1.0);
+}
+
+// Integrates the Lorenz system from a random point with RK4 steps of dt,
+// adding the x-z projection of max_iter of its points to the density once it
+// settled on the attractor.
+fn lorenz_sample(state: ptr<function, u32>) {
+    var p = vec3<f32>(
+        20.0 * random(state) - 10.0,
+        20.0 * random(state) - 10.0,
+        20.0 * random(state) + 10.0,
+    );
+    let dt = f32(fdata.dt);
+    let warmup = fdata.max_iter / 10u;
+    for (var n = 0u; n < warmup + fdata.max_iter; n = n + 1u) {
+        let k1 = lorenz(p);
+        let k2 = lorenz(p + 0.5 * dt * k1);
+        let k3 = lorenz(p + 0.5 * dt * k2);
+        let k4 = lorenz(p + dt * k3);
+        p = p + dt / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4);
+
+        if n >= warmup {
+            splat(vec2float(p.xz), vec3<bool>(true));
+        }
+    }
+}
+
+// Integrates the selected forced oscillator from a random point of the view,
+// adding its samples at the phase section_phase of max_iter forcing periods to
+// the density once it settled, which draws the Poincaré section of its
+// attractor.
+fn poincare_sample(state: ptr<function, u32>) {
+    var z = vec2float(
+        mix(fdata.x_range[0], fdata.x_range[1], float(random(state))),
+        mix(fdata.y_range[0], fdata.y_range[1], float(random(state))),
+    );
+    let period = float(6.2831853 / forcing.y);
+    // Bounds the number of steps per period at low frequencies
+    let min_step = period / float(1024.0);
+    let warmup = fdata.max_iter / 10u;
+
+    var h = fdata.dt;
+    var t = float(0.0);
+    for (var n = 0u; n < warmup + fdata.max_iter; n = n + 1u) {
+        // Integrate up to the section, shortening the last step to land on it
+        let end = (float(n) + float(fdata.section_phase)) * period;
+        while end - t > float(1e-6) * period {
+            var step = min(max(h, min_step), end - t);
+            z = integrate(z, &step, &t);
+            if fdata.integrator == INTEGRATOR_DORMAND_PRINCE {
+                h = step;
+            }
+            if dot(z, z) > float(1e6) {
+                return;
+            }
+        }
+
+        if n >= warmup {
+            splat(z, vec3<bool>(true));
+        }
+    }
+}
+
+// The Lorenz system, with sigma, rho and beta in the parameters
+fn lorenz(p: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        fdata.param_a * (p.y - p.x),
+        p.x * (fdata.param_b - p.z) - p.y,
+        p.x * p.y - fdata.param_c * p.z,
+    );
+}
+
+// Adds a point to the selected channels of the density of the pixel it lands
+// in, if any
+fn splat(z: vec2float, channels: vec3<bool>) {
+    let dims = textureDimensions(tex);
+    let uv = vec2<f32>(
+        f32((z.x - fdata.x_range[0]) / (fdata.x_range[1] - fdata.x_range[0])),
+        f32((z.y - fdata.y_range[0]) / (fdata.y_range[1] - fdata.y_range[0])),
+    );
+    if any(uv < vec2<f32>(0.0)) || any(uv >= vec2<f32>(1.0)) {
+        return;
+    }
+
+    // Flip Y, like the pixels of cs_main
+    let pixel = vec2<u32>(vec2<f32>(uv.x, 1.0 - uv.y) * vec2<f32>(dims));
+    let index = 3u * (min(pixel.y, dims.y - 1u) * dims.x + pixel.x);
+    for (var channel = 0u; channel < 3u; channel = channel + 1u) {
+        if channels[channel] {
+            atomicAdd(&density[index + channel], 1u);
+        }
+    }
+}
+
+// Colors a pixel of the density kernels by the logarithm of its density,
+// which the post-processing normalizes
+fn density_color(pixel: vec2<u32>, dims: vec2<u32>) -> vec4<f32> {
+    let index = 3u * (pixel.y * dims.x + pixel.x);
+    let counts = vec3<f32>(
+        f32(atomicLoad(&density[index])),
+        f32(atomicLoad(&density[index + 1u])),
+        f32(atomicLoad(&density[index + 2u])),
+    );
+    return vec4<f32>(log(vec3<f32>(1.0) + counts), 1.0);
+}
+
+// Hashes a 32-bit integer with the PCG permutation
+fn pcg_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Returns a uniform random number in [0, 1), advancing the state
+fn random(state: ptr<function, u32>) -> f32 {
+    *state = pcg_hash(*state);
+    return f32(*state >> 8u) / 16777216.0;
+}
+
+// Estimates the Lyapunov exponent of the logistic map x -> r x (1 - x), where
+// r follows the A/B sequence of lyapunov_data with r = a for A and r = b for B.
+// Negative exponents, where the orbit is stable, are colored in yellow and
+// positive ones, where it is chaotic, in blue.
+fn lyapunov(a: float, b: float) -> vec4<f32> {
+    // Start from the critical point of the map
+    var x = float(0.5);
+    var step = 0u;
+    var sum = 0.0;
+    let total = lyapunov_data.warmup + fdata.max_iter;
+
+    for (var n = 0u; n < total; n = n + 1u) {
+        var r = a;
+        if lyapunov_data.sequence[step] != 0u {
+            r = b;
+        }
+        step = step + 1u;
+        if step >= lyapunov_data.sequence_len {
+            step = 0u;
+        }
+
+        // The exponent averages log|f'(x)| along the orbit, once it settled
+        if n >= lyapunov_data.warmup {
+            sum = sum + log(max(abs(f32(r * (float(1.0) - float(2.0) * x))), 1e-30));
+        }
+        x = r * x * (float(1.0) - x);
+    }
+
+    let exponent = sum / f32(max(fdata.max_iter, 1u));
+    if exponent < 0.0 {
+        return vec4<f32>(hsv2rgb(0.15, 1.0, 1.0 - exp(exponent)), 1.0);
+    }
+    return vec4<f32>(hsv2rgb(0.6, 1.0, 1.0 - exp(-exponent)), 1.0);
+}
+
+// Applies Newton's method to the polynomial whose roots are in ndata and
+// colors by the root reached and the number of iterations needed
+fn newton(z_initial: vec2float) -> vec4<f32> {
+    let num_roots = min(ndata.num_roots, MAX_NEWTON_ROOTS);
+    if num_roots == 0u {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let one = vec2float(float(1.0), float(0.0));
+    let tolerance = float(1e-6);
+    var z = z_initial;
+
+    for (var iter = 0u; iter < fdata.max_iter; iter = iter + 1u) {
+        // For p(z) = prod(z - r_i), we have p'(z) / p(z) = sum(1 / (z - r_i))
+        var sum = vec2float(float(0.0), float(0.0));
+        for (var i = 0u; i < num_roots; i = i + 1u) {
+            let d = z - vec2float(ndata.roots[i]);
+
+            // Check for convergence to the root
+            if dot(d, d) < tolerance {
+                // One hue per root, shaded by the iteration count
+                let h = f32(i) / f32(num_roots);
+                let v = 1.0 - log2(f32(iter) + 1.0) / log2(f32(fdata.max_iter) + 1.0);
+                return vec4<f32>(hsv2rgb(h, 1.0, v), 1.0);
+            }
+
+            sum = sum + cx_div_float(one, d);
+        }
+
+        // Compute next iteration: z = z - a * p(z) / p'(z)
+        z = z - float(ndata.relaxation) * cx_div_float(one, sum);
+    }
+
+    // Never converged → black
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
 }
 
 // We’ll sample f(x ± h) to approximate f′(x):
+// The function is the user formula, generated in f32
 fn f(x: float) -> float {
-    return -x * cos(exp(sin(float(10.0) * x)) * x);
+    return float(formula_function(f32(x)));
 }
 
 fn math_fn(x: float, y: float, dx: float, dy: float, thickness: float) -> vec4<f32> {
@@ -140,6 +1299,88 @@ fn math_fn(x: float, y: float, dx: float, dy: float, thickness: float) -> vec4<f
     return mix(bg, fg, final_alpha);
 }
 
+// Returns whether the selected kernel integrates a 2D vector field, like
+// `Kernel::is_flow`
+fn is_flow_kernel() -> bool {
+    return fdata.kernel == KERNEL_VAN_DER_POL || fdata.kernel == KERNEL_FLOW_FORMULA
+        || fdata.kernel == KERNEL_DUFFING || fdata.kernel == KERNEL_PENDULUM
+        || fdata.kernel == KERNEL_MAGNETIC_PENDULUM || fdata.kernel == KERNEL_LOTKA_VOLTERRA
+        || fdata.kernel == KERNEL_FITZHUGH_NAGUMO || is_forced_kernel();
+}
+
+// Returns whether the selected kernel is a forced oscillator, like
+// `Kernel::is_forced`
+fn is_forced_kernel() -> bool {
+    return fdata.kernel == KERNEL_FORCED_VAN_DER_POL || fdata.kernel == KERNEL_FORCED_DUFFING;
+}
+
+// Returns the derivative of the selected flow at z and time t, which adds the
+// periodic forcing of the forced oscillators to their unforced field. The
+// cosine is taken in f32.
+fn flow_field(z: vec2float, t: float) -> vec2float {
+    var field = flow_step(z);
+    if is_forced_kernel() {
+        field.y = field.y + float(forcing.x * cos(forcing.y * f32(t)));
+    }
+    return field;
+}
+
+// Returns the derivative of the selected flow at z, without the forcing of
+// the forced oscillators
+fn flow_step(z: vec2float) -> vec2float {
+    let a = float(fdata.param_a);
+    let b = float(fdata.param_b);
+    let c = float(fdata.param_c);
+    let d = float(fdata.param_d);
+    if fdata.kernel == KERNEL_FLOW_FORMULA {
+        // User formula, generated in f32
+        return vec2float(formula_flow(f32(z.x), f32(z.y), f32(fdata.mu)));
+    } else if fdata.kernel == KERNEL_DUFFING || fdata.kernel == KERNEL_FORCED_DUFFING {
+        // Damped Duffing oscillator x'' = -delta x' - alpha x - beta x^3,
+        // with two wells for negative alpha
+        return vec2float(z.y, -a * z.y - b * z.x - c * z.x * z.x * z.x);
+    } else if fdata.kernel == KERNEL_PENDULUM {
+        // Damped pendulum with a constant torque, whose sine is taken in f32
+        return vec2float(z.y, -a * z.y - float(sin(f32(z.x))) + b);
+    } else if fdata.kernel == KERNEL_MAGNETIC_PENDULUM {
+        return vec2float(magnetic_pendulum(vec2<f32>(z)));
+    } else if fdata.kernel == KERNEL_LOTKA_VOLTERRA {
+        // Two competing species, each limiting the growth of the other
+        return vec2float(
+            z.x * (float(1.0) - z.x - a * z.y),
+            z.y * (float(1.0) - z.y - b * z.x),
+        );
+    } else if fdata.kernel == KERNEL_FITZHUGH_NAGUMO {
+        // Membrane potential v = x and recovery variable w = y, driven by a
+        // current
+        return vec2float(
+            z.x - z.x * z.x * z.x / float(3.0) - z.y + d,
+            c * (z.x + a - b * z.y),
+        );
+    }
+    return step_vdp(z);
+}
+
+// Overdamped magnetic pendulum, whose bob is pulled back to the center by a
+// spring and attracted by three magnets on the unit circle, at a height above
+// its plane
+fn magnetic_pendulum(z: vec2<f32>) -> vec2<f32> {
+    let height = fdata.param_b;
+    var force = -fdata.param_a * z;
+    for (var i = 0u; i < 3u; i = i + 1u) {
+        let delta = magnet(i) - z;
+        let r2 = dot(delta, delta) + height * height;
+        force = force + delta / (r2 * sqrt(r2));
+    }
+    return force;
+}
+
+// Returns the position of the i-th magnet of the magnetic pendulum
+fn magnet(i: u32) -> vec2<f32> {
+    let angle = 2.0943951 * f32(i);
+    return vec2<f32>(cos(angle), sin(angle));
+}
+
 fn step_vdp(z: vec2float) -> vec2float {
     // z.x = x, z.y = y
     let x = z.x;
@@ -153,13 +1394,230 @@ fn step_vdp(z: vec2float) -> vec2float {
     );
 }
 
+// Advances z by one step of the selected integrator along the flow, and the
+// time t by the size of the step, which mirrors `Integrator::adaptive_step`.
+// The adaptive integrator updates the step size h following its error
+// estimate, the others keep it.
+fn integrate(z: vec2float, h: ptr<function, float>, t: ptr<function, float>) -> vec2float {
+    let dt = *h;
+    let t0 = *t;
+    if fdata.integrator != INTEGRATOR_DORMAND_PRINCE {
+        *t = t0 + dt;
+    }
+    let half = float(0.5) * dt;
+    if fdata.integrator == INTEGRATOR_MIDPOINT {
+        return z + dt * flow_field(z + half * flow_field(z, t0), t0 + half);
+    } else if fdata.integrator == INTEGRATOR_RK4 {
+        let k1 = flow_field(z, t0);
+        let k2 = flow_field(z + half * k1, t0 + half);
+        let k3 = flow_field(z + half * k2, t0 + half);
+        let k4 = flow_field(z + dt * k3, t0 + dt);
+        return z + dt / float(6.0) * (k1 + float(2.0) * k2 + float(2.0) * k3 + k4);
+    } else if fdata.integrator == INTEGRATOR_DORMAND_PRINCE {
+        return dormand_prince(z, h, t);
+    } else if fdata.integrator == INTEGRATOR_LEAPFROG {
+        // Kick the momentum y by half a step, drift the position x by a whole
+        // step, then kick the momentum again
+        let y = z.y + half * flow_field(z, t0).y;
+        let x = z.x + dt * flow_field(vec2float(z.x, y), t0 + half).x;
+        return vec2float(x, y + half * flow_field(vec2float(x, y), t0 + dt).y);
+    }
+    return z + dt * flow_field(z, t0);
+}
+
+// Takes a step of the Dormand-Prince method from z, retried with a smaller
+// size while its estimated error exceeds the tolerance, at most
+// MAX_REJECTED_STEPS times, and proposes the size of the next step in h
+fn dormand_prince(z: vec2float, h: ptr<function, float>, t: ptr<function, float>) -> vec2float {
+    let scale = fdata.tolerance * (1.0 + f32(length(z)));
+    var next = z;
+    var taken = *h;
+    for (var attempt = 0u; attempt <= MAX_REJECTED_STEPS; attempt = attempt + 1u) {
+        let dt = *h;
+        var error: vec2float;
+        next = dormand_prince_step(z, *t, dt, &error);
+        taken = dt;
+
+        // The error of the order 4 solution scales as dt^5, and the powers
+        // of f64 are not defined by WGSL
+        let ratio = f32(length(error)) / scale;
+        let factor = clamp(0.9 * pow(max(ratio, 1e-10), -0.2), 0.2, 5.0);
+        *h = dt * float(factor);
+        if ratio <= 1.0 {
+            break;
+        }
+    }
+    *t = *t + taken;
+    return next;
+}
+
+// Takes a step of size dt of the Dormand-Prince method from z at time t,
+// returning the order 5 solution and its difference with the order 4 solution
+// in error. The coefficients are divided in the precision of the shader.
+fn dormand_prince_step(
+    z: vec2float,
+    t: float,
+    dt: float,
+    error: ptr<function, vec2float>,
+) -> vec2float {
+    let k1 = flow_field(z, t);
+    let k2 = flow_field(z + dt * k1 / float(5.0), t + dt / float(5.0));
+    let k3 = flow_field(
+        z + dt * (float(3.0) * k1 + float(9.0) * k2) / float(40.0),
+        t + dt * float(3.0) / float(10.0),
+    );
+    let k4 = flow_field(z + dt * (
+        float(44.0) / float(45.0) * k1
+            - float(56.0) / float(15.0) * k2
+            + float(32.0) / float(9.0) * k3
+    ), t + dt * float(4.0) / float(5.0));
+    let k5 = flow_field(z + dt * (
+        float(19372.0) / float(6561.0) * k1
+            - float(25360.0) / float(2187.0) * k2
+            + float(64448.0) / float(6561.0) * k3
+            - float(212.0) / float(729.0) * k4
+    ), t + dt * float(8.0) / float(9.0));
+    let k6 = flow_field(z + dt * (
+        float(9017.0) / float(3168.0) * k1
+            - float(355.0) / float(33.0) * k2
+            + float(46732.0) / float(5247.0) * k3
+            + float(49.0) / float(176.0) * k4
+            - float(5103.0) / float(18656.0) * k5
+    ), t + dt);
+    let next = z + dt * (
+        float(35.0) / float(384.0) * k1
+            + float(500.0) / float(1113.0) * k3
+            + float(125.0) / float(192.0) * k4
+            - float(2187.0) / float(6784.0) * k5
+            + float(11.0) / float(84.0) * k6
+    );
+    let k7 = flow_field(next, t + dt);
+    *error = dt * (
+        float(71.0) / float(57600.0) * k1
+            - float(71.0) / float(16695.0) * k3
+            + float(71.0) / float(1920.0) * k4
+            - float(17253.0) / float(339200.0) * k5
+            + float(22.0) / float(525.0) * k6
+            - float(1.0) / float(40.0) * k7
+    );
+    return next;
+}
+
+// Integrates the trajectory of a pixel until it settles on a fixed point of
+// the flow, and colors the pixel with a hue per fixed point, darkened by the
+// time it took to settle. Trajectories which leave far away are black, and
+// those which never settle, such as those attracted by a limit cycle, are
+// gray.
+fn basins(initial: vec2float) -> vec4<f32> {
+    var z = initial;
+    var h = fdata.dt;
+    var t = float(0.0);
+    for (var iter = 0u; iter < fdata.max_iter; iter = iter + 1u) {
+        z = integrate(z, &h, &t);
+        if dot(z, z) > float(1e6) {
+            return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        }
+
+        let speed = f32(length(flow_step(z)));
+        if speed < 1e-3 * (1.0 + f32(length(z))) {
+            let t = f32(iter) / f32(fdata.max_iter);
+            return vec4<f32>(hsv2rgb(fixed_point_hue(z), 1.0, 1.0 - 0.75 * sqrt(t)), 1.0);
+        }
+    }
+    return vec4<f32>(0.25, 0.25, 0.25, 1.0);
+}
+
+// Integrates the trajectory of a pixel until it comes within the tolerance of
+// the limit cycle of the Van der Pol oscillator, and colors the pixel by its
+// asymptotic phase, the phase on arrival minus the elapsed time, which is
+// constant along the isochrons of the cycle. The pixel is darkened by the
+// time it took to arrive, and black if it never did.
+fn limit_cycle_color(initial: vec2float) -> vec4<f32> {
+    var z = initial;
+    var h = fdata.dt;
+    var t = float(0.0);
+    for (var iter = 0u; iter < fdata.max_iter; iter = iter + 1u) {
+        let nearest = cycle_nearest(vec2<f32>(z));
+        if nearest.x < fdata.cycle_tolerance {
+            let phase = fract(nearest.y - f32(t) / limit_cycle.period);
+            let v = 1.0 - 0.75 * sqrt(f32(iter) / f32(fdata.max_iter));
+            return vec4<f32>(hsv2rgb(phase, 1.0, v), 1.0);
+        }
+
+        z = integrate(z, &h, &t);
+        if dot(z, z) > float(1e6) {
+            break;
+        }
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
+
+// Returns the distance from p to the nearest point of the limit cycle, and
+// the phase of that point, interpolated between the points of the cycle
+fn cycle_nearest(p: vec2<f32>) -> vec2<f32> {
+    var nearest = vec2<f32>(1e30, 0.0);
+    for (var i = 0u; i < LIMIT_CYCLE_LEN; i = i + 1u) {
+        let a = limit_cycle.points[i];
+        let ab = limit_cycle.points[(i + 1u) % LIMIT_CYCLE_LEN] - a;
+        let f = clamp(dot(p - a, ab) / max(dot(ab, ab), 1e-20), 0.0, 1.0);
+        let d = distance(p, a + f * ab);
+        if d < nearest.x {
+            nearest = vec2<f32>(d, (f32(i) + f) / f32(LIMIT_CYCLE_LEN));
+        }
+    }
+    return nearest;
+}
+
+// Returns the hue of a fixed point, telling apart the attractors of each
+// system, or hashing the position of the fixed point for the flows whose
+// attractors are not known
+fn fixed_point_hue(z: vec2float) -> f32 {
+    let p = vec2<f32>(z);
+    var index = 0;
+    if fdata.kernel == KERNEL_DUFFING {
+        // Bottom of the right or left well, or the center for a single well
+        if p.x > 0.01 {
+            index = 1;
+        } else if p.x < -0.01 {
+            index = 2;
+        }
+    } else if fdata.kernel == KERNEL_PENDULUM {
+        // Number of turns made before resting
+        index = i32(round(p.x / 6.2831855));
+    } else if fdata.kernel == KERNEL_MAGNETIC_PENDULUM {
+        // Nearest magnet
+        var nearest = distance(p, magnet(0u));
+        for (var i = 1u; i < 3u; i = i + 1u) {
+            if distance(p, magnet(i)) < nearest {
+                nearest = distance(p, magnet(i));
+                index = i32(i);
+            }
+        }
+    } else if fdata.kernel == KERNEL_LOTKA_VOLTERRA {
+        // Surviving species, or coexistence
+        if p.y < 0.01 {
+            index = 0;
+        } else if p.x < 0.01 {
+            index = 1;
+        } else {
+            index = 2;
+        }
+    } else {
+        let cell = vec2<i32>(round(p * 8.0));
+        return f32(pcg_hash(bitcast<u32>(cell.x) ^ pcg_hash(bitcast<u32>(cell.y))) >> 8u)
+            / 16777216.0;
+    }
+    // Spread consecutive indices around the hue circle with the golden ratio
+    return fract(f32(index) * 0.618034);
+}
+
 fn van_der_pol(initial: vec2float) -> vec4<f32> {
     var z = initial;
+    var h = fdata.dt;
+    var t = float(0.0);
     var iter = 0u;
     while (iter < fdata.max_iter) {
-        // Single Euler step (feel free to swap in RK4 for more accuracy)
-        let dz = step_vdp(z);
-        z = z + fdata.dt * dz;
+        z = integrate(z, &h, &t);
 
         // divergence test
         if (dot(z, z) > float(200.0)) {
@@ -180,24 +1638,259 @@ fn van_der_pol(initial: vec2float) -> vec4<f32> {
     return vec4<f32>(shade, shade, shade, 1.0);
 }
 
-fn hsv2rgb(h: f32, s: f32, v: f32) -> vec3f {
-    let c = v * s;
-    let hp = fract(h) * 6.0;
-    let x = c * (1.0 - abs(fract(hp) * 2.0 - 1.0));
-    var rgb = vec3f(0.0);
-    if hp < 1.0 {
-        rgb = vec3f(c, x, 0.0);
-    } else if hp < 2.0 {
-        rgb = vec3f(x, c, 0.0);
-    } else if hp < 3.0 {
-        rgb = vec3f(0.0, c, x);
-    } else if hp < 4.0 {
-        rgb = vec3f(0.0, x, c);
-    } else if hp < 5.0 {
-        rgb = vec3f(x, 0.0, c);
-    } else {
-        rgb = vec3f(c, 0.0, x);
+// Integrates the forced oscillator from (1, 0) with the forcing frequency and
+// amplitude of the pixel, and colors the pixel by the ratio p:q its
+// oscillation locks to, for which the trajectory sampled once per forcing
+// period returns to the same sample every q periods, after p turns around the
+// origin. The samples are compared over the last MAX_LOCKING_PERIOD periods
+// of max_iter, the first half of which lets the trajectory settle, and the
+// trajectories which are not locked are shaded in gray by their rotation
+// number, the number of turns per forcing period.
+fn arnold_tongues(frequency: float, amplitude: float) -> vec4<f32> {
+    if frequency <= float(0.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    forcing = vec2<f32>(f32(amplitude), f32(frequency));
+    let period = float(6.2831853) / frequency;
+    // Bounds the number of steps per period at low frequencies
+    let min_step = period / float(1024.0);
+    let settle = fdata.max_iter / 2u;
+
+    var z = vec2float(float(1.0), float(0.0));
+    var h = fdata.dt;
+    var t = float(0.0);
+    // Angle turned clockwise around the origin since the trajectory settled,
+    // which is the direction the oscillators turn in
+    var winding = 0.0;
+    var samples: array<vec2float, MAX_LOCKING_PERIOD>;
+    var windings: array<f32, MAX_LOCKING_PERIOD>;
+    for (var n = 1u; n <= fdata.max_iter; n = n + 1u) {
+        // Integrate up to the end of the period, shortening the last step to
+        // land on it
+        let end = float(n) * period;
+        while end - t > float(1e-6) * period {
+            let angle = atan2(-f32(z.y), f32(z.x));
+            var step = min(max(h, min_step), end - t);
+            z = integrate(z, &step, &t);
+            if fdata.integrator == INTEGRATOR_DORMAND_PRINCE {
+                h = step;
+            }
+            if dot(z, z) > float(1e6) {
+                return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+            }
+            if n > settle {
+                let turn = atan2(-f32(z.y), f32(z.x)) - angle;
+                winding = winding + turn - 6.2831853 * round(turn / 6.2831853);
+            }
+        }
+
+        // Compare the last sample to those of the previous periods, the
+        // shortest period first
+        if n == fdata.max_iter {
+            let scale = LOCKING_TOLERANCE * (1.0 + f32(length(z)));
+            for (var q = 1u; q <= MAX_LOCKING_PERIOD && q < n - settle; q = q + 1u) {
+                let i = (n - q) % MAX_LOCKING_PERIOD;
+                if f32(distance(z, samples[i])) < scale {
+                    let p = round((winding - windings[i]) / 6.2831853);
+                    // The hue follows the ratio, and the tongues of longer
+                    // periods are darker
+                    let hue = fract(0.75 * p / f32(q));
+                    return vec4<f32>(hsv2rgb(hue, 0.8, 1.0 - 0.08 * f32(q - 1u)), 1.0);
+                }
+            }
+        }
+        samples[n % MAX_LOCKING_PERIOD] = z;
+        windings[n % MAX_LOCKING_PERIOD] = winding;
+    }
+
+    let rotation = winding / (6.2831853 * f32(fdata.max_iter - settle));
+    let shade = 0.15 + 0.35 * fract(rotation);
+    return vec4<f32>(shade, shade, shade, 1.0);
+}
+
+// Convolves white noise along the streamline of the flow through p, following
+// Cabral and Leedom. The streamline is traced with midpoint steps of one pixel,
+// lic_length steps in each direction, and the noise is sampled on the pixel
+// grid of the world so that it follows the view when panning.
+fn lic(p: vec2float, dx: float, dy: float) -> vec4<f32> {
+    let pixel = vec2<f32>(f32(dx), f32(dy));
+    let start = vec2<f32>(p) / pixel;
+
+    var sum = lic_noise(start);
+    var count = 1.0;
+    for (var side = 0u; side < 2u; side = side + 1u) {
+        let forward = 1.0 - 2.0 * f32(side);
+        var q = start;
+        for (var n = 0u; n < fdata.lic_length; n = n + 1u) {
+            let k1 = forward * lic_direction(q, pixel);
+            let k2 = forward * lic_direction(q + 0.5 * k1, pixel);
+            // The streamline stops at the fixed points of the flow
+            if all(k2 == vec2<f32>(0.0)) {
+                break;
+            }
+            q = q + k2;
+            sum = sum + lic_noise(q);
+            count = count + 1.0;
+        }
+    }
+
+    // Averaging flattens the noise, whose contrast is stretched back by the
+    // square root of the number of samples, halved as neighboring samples
+    // are correlated
+    let value = clamp(0.5 + (sum / count - 0.5) * 0.5 * sqrt(count), 0.0, 1.0);
+
+    var tint = vec3<f32>(1.0);
+    if fdata.lic_coloring == LIC_COLORING_SPEED {
+        let speed = f32(length(flow_step(p)));
+        tint = hsv2rgb(0.66 * (1.0 - speed / (1.0 + speed)), 1.0, 1.0);
+    } else if fdata.lic_coloring == LIC_COLORING_DIVERGENCE {
+        // Central differences of the field over one pixel
+        let ex = vec2float(dx, float(0.0));
+        let ey = vec2float(float(0.0), dy);
+        let divergence = f32(
+            (flow_step(p + ex).x - flow_step(p - ex).x) / (float(2.0) * dx)
+                + (flow_step(p + ey).y - flow_step(p - ey).y) / (float(2.0) * dy)
+        );
+        let t = divergence / (1.0 + abs(divergence));
+        if t > 0.0 {
+            tint = mix(vec3<f32>(1.0), vec3<f32>(1.0, 0.0, 0.0), t);
+        } else {
+            tint = mix(vec3<f32>(1.0), vec3<f32>(0.0, 0.0, 1.0), -t);
+        }
+    }
+    return vec4<f32>(value * tint, 1.0);
+}
+
+// Returns the direction of the flow at q, in pixels, as a step of one pixel,
+// which is zero at the fixed points
+fn lic_direction(q: vec2<f32>, pixel: vec2<f32>) -> vec2<f32> {
+    let v = vec2<f32>(flow_step(vec2float(q * pixel))) / pixel;
+    let len = length(v);
+    if len == 0.0 {
+        return vec2<f32>(0.0);
     }
-    let m = v - c;
-    return rgb + vec3<f32>(m);
+    return v / len;
+}
+
+// Samples white noise hashed from the pixel grid at q, in pixels, interpolated
+// bilinearly between the pixels
+fn lic_noise(q: vec2<f32>) -> f32 {
+    let cell = floor(q);
+    let f = q - cell;
+    let i = vec2<i32>(cell);
+    let n00 = lattice_noise(i);
+    let n10 = lattice_noise(i + vec2<i32>(1, 0));
+    let n01 = lattice_noise(i + vec2<i32>(0, 1));
+    let n11 = lattice_noise(i + vec2<i32>(1, 1));
+    return mix(mix(n00, n10, f.x), mix(n01, n11, f.x), f.y);
+}
+
+// Returns a uniform random number in [0, 1) for a point of the pixel grid
+fn lattice_noise(i: vec2<i32>) -> f32 {
+    return f32(pcg_hash(bitcast<u32>(i.x) ^ pcg_hash(bitcast<u32>(i.y))) >> 8u) / 16777216.0;
+}
+
+// Double-single numbers are stored as vec2<f32>(hi, lo), with |lo| at most
+// half an ulp of hi, and represent the unevaluated sum hi + lo
+
+// Returns the sum of a and b as a double-single number, exactly
+fn two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = a + b;
+    let bb = s - a;
+    let err = (a - (s - bb)) + (b - bb);
+    return vec2<f32>(s, err);
+}
+
+// Same as two_sum, assuming |a| >= |b|
+fn quick_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = a + b;
+    let err = b - (s - a);
+    return vec2<f32>(s, err);
+}
+
+// Splits a into two halves of 12 bits each, such that a = hi + lo
+fn ds_split(a: f32) -> vec2<f32> {
+    let t = 4097.0 * a;
+    let hi = t - (t - a);
+    return vec2<f32>(hi, a - hi);
+}
+
+// Returns the product of a and b as a double-single number, exactly
+fn two_prod(a: f32, b: f32) -> vec2<f32> {
+    let p = a * b;
+    let sa = ds_split(a);
+    let sb = ds_split(b);
+    let err = ((sa.x * sb.x - p) + sa.x * sb.y + sa.y * sb.x) + sa.y * sb.y;
+    return vec2<f32>(p, err);
+}
+
+fn ds_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    var s = two_sum(a.x, b.x);
+    let t = two_sum(a.y, b.y);
+    s.y = s.y + t.x;
+    s = quick_two_sum(s.x, s.y);
+    s.y = s.y + t.y;
+    return quick_two_sum(s.x, s.y);
+}
+
+fn ds_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return ds_add(a, -b);
+}
+
+fn ds_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    var p = two_prod(a.x, b.x);
+    p.y = p.y + (a.x * b.y + a.y * b.x);
+    return quick_two_sum(p.x, p.y);
+}
+
+// Returns 2^e, flushed to 0 below the normal range of f32 and saturated at
+// the largest power of two above it
+fn exp2i(e: i32) -> f32 {
+    if e < -126 {
+        return 0.0;
+    }
+    return bitcast<f32>(u32(min(e, 127) + 127) << 23u);
+}
+
+// Returns log2 |v| for a vector of any magnitude, or -1e30 if v is 0 or
+// subnormal
+fn log2_length(v: vec2<f32>) -> f32 {
+    let largest = max(abs(v.x), abs(v.y));
+    let e = exponent_of(largest);
+    if e == 0 && largest < 0.5 {
+        return -1e30;
+    }
+    return log2(length(scale_exp2(v, -e))) + f32(e);
+}
+
+// Returns the exponent of the largest component of v, or a very small
+// exponent if v is 0 or subnormal so that it is never used as scale
+fn vec_exponent(v: vec2<f32>) -> i32 {
+    let largest = max(abs(v.x), abs(v.y));
+    if largest < 1.17549435e-38 {
+        return -100000;
+    }
+    return exponent_of(largest);
+}
+
+// Returns v * 2^e, computed in two steps so that the power of two is not
+// saturated by exp2i for any e the result fits in f32
+fn scale_exp2(v: vec2<f32>, e: i32) -> vec2<f32> {
+    let half = e / 2;
+    return v * exp2i(half) * exp2i(e - half);
+}
+
+// Scales w so that its largest component is in [0.5, 1), updating its
+// exponent k so that w * 2^k is unchanged
+fn renormalize(w: ptr<function, vec2<f32>>, k: ptr<function, i32>) {
+    let e = exponent_of(max(abs((*w).x), abs((*w).y)));
+    *w = scale_exp2(*w, -e);
+    *k = *k + e;
+}
+
+// Returns the exponent e such that x = f * 2^e with 0.5 <= |f| < 1, or 0 if
+// x is 0 or subnormal
+fn exponent_of(x: f32) -> i32 {
+    let biased = i32((bitcast<u32>(x) >> 23u) & 0xffu);
+    return select(biased - 126, 0, biased == 0);
 }
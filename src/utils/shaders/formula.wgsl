@@ -0,0 +1,28 @@
+// Real helpers used by the WGSL code generated from user formulas, see
+// formula.rs. Its complex helpers are those of complex.wgsl
+
+// Raises x to an integer power n by repeated squaring
+fn rx_powi(x: f32, n: i32) -> f32 {
+    var base = x;
+    var e = abs(n);
+    var result = 1.0;
+    while e > 0 {
+        if (e & 1) != 0 {
+            result = result * base;
+        }
+        base = base * base;
+        e = e >> 1u;
+    }
+    return select(result, 1.0 / result, n < 0);
+}
+
+// Raises x to a real power y. pow is undefined for a negative x, whose
+// integer powers are computed from |x| instead, like powf on the CPU
+fn rx_pow(x: f32, y: f32) -> f32 {
+    if x < 0.0 && y == floor(y) {
+        // Odd powers keep the sign of x
+        let magnitude = pow(-x, y);
+        return select(magnitude, -magnitude, fract(0.5 * y) != 0.0);
+    }
+    return pow(x, y);
+}
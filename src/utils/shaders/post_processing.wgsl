@@ -1,3 +1,5 @@
+#include "common.wgsl"
+
 struct GlobalData {
     value_min: atomic<u32>, // Bitcast from f32
     value_max: atomic<u32>, // Bitcast from f32
@@ -13,10 +15,10 @@ var tex: texture_storage_2d<rgba32float, read_write>;
 @group(0) @binding(2)
 var<storage, read_write> gdata: GlobalData;
 
-const WG_SIZE = 256u; // Workgroup size
+const WG_SIZE = WORKGROUP_INVOCATIONS; // Workgroup size
 var<workgroup> local_mins: array<f32, WG_SIZE>;
 var<workgroup> local_maxs: array<f32, WG_SIZE>;
-@compute @workgroup_size(16, 16)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn cs_min_max(
     @builtin(global_invocation_id) gid: vec3<u32>,
     @builtin(local_invocation_index) lidx: u32
@@ -62,7 +64,7 @@ fn cs_min_max(
     }
 }
 
-@compute @workgroup_size(16,16)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn cs_recalibrate(
     @builtin(global_invocation_id) gid: vec3<u32>,
 ) {
@@ -88,7 +90,7 @@ fn cs_recalibrate(
     textureStore(tex, vec2<u32>(gid.xy),  vec4<f32>(normalized, color.a));
 }
 
-@compute @workgroup_size(16, 16)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn cs_histogram(
     @builtin(global_invocation_id) gid: vec3<u32>,
 ) {
@@ -131,7 +133,7 @@ fn cs_cdf(
     gdata.cdf_non_zero = first_non_zero;
 }
 
-@compute @workgroup_size(16, 16)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn cs_equalize(
     @builtin(global_invocation_id) gid: vec3<u32>,
 ) {
@@ -157,10 +159,3 @@ fn cs_equalize(
     let rgb = clamp(color.rgb * scale, vec3<f32>(0.0), vec3<f32>(1.0));
     textureStore(tex, vec2<i32>(gid.xy), vec4<f32>(rgb, color.a));
 }
-
-fn get_luminance(color: vec4<f32>) -> f32 {
-    // Compute a scalar luminance/brightness from RGB
-    // return max(max(color.r, color.g), color.b);
-    // Alternatively, for perceptual luminance:
-    return dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
-}
@@ -0,0 +1,86 @@
+use faraday_art::utils::{
+    bla::BlaTable,
+    perturbation::{DeepZoomView, escape_iterations},
+};
+
+/// Number of pixels per side of the grid of points compared.
+const GRID_SIZE: usize = 48;
+
+/// Compares the escape times of a grid of points around `center`, iterated
+/// with and without the bilinear approximation.
+///
+/// # Returns
+///
+/// - The fraction of points with the same escape time with the approximation.
+/// - The fraction of points with the same escape time when their offset is
+///   rounded to `f32` precision instead, which measures how chaotic the view
+///   is.
+fn compare_escape_times(center: (f64, f64), size: f64, max_iter: u32) -> (f64, f64) {
+    // Start from a shallow view, as the ranges of deep views cannot be
+    // represented with f64
+    let mut view = DeepZoomView::from_ranges(
+        (center.0 - 0.5, center.0 + 0.5),
+        (center.1 - 0.5, center.1 + 0.5),
+    );
+    view.zoom_relative(size, (0.5, 0.5));
+    let reference_orbit = view.reference_orbit(max_iter, 2.0);
+    let max_dc = std::f64::consts::FRAC_1_SQRT_2 * size;
+    let table = BlaTable::new(&reference_orbit, max_dc);
+
+    // Make sure that iterations are actually skipped
+    let (_, skip) = table
+        .lookup(1, max_dc.log2() as f32, max_iter)
+        .expect("no valid step for the first iteration");
+    assert!(skip > 1);
+
+    let (mut matching, mut matching_rounded) = (0, 0);
+    for i in 0..GRID_SIZE {
+        for j in 0..GRID_SIZE {
+            let dc = (
+                (i as f64 / (GRID_SIZE - 1) as f64 - 0.5) * size,
+                (j as f64 / (GRID_SIZE - 1) as f64 - 0.5) * size,
+            );
+            let full = escape_iterations(&reference_orbit, dc, max_iter, 2.0, None);
+            let skipped = escape_iterations(&reference_orbit, dc, max_iter, 2.0, Some(&table));
+            let rounded = (dc.0 as f32 as f64, dc.1 as f32 as f64);
+            let rounded = escape_iterations(&reference_orbit, rounded, max_iter, 2.0, None);
+            if full == skipped {
+                matching += 1;
+            }
+            if full == rounded {
+                matching_rounded += 1;
+            }
+        }
+    }
+
+    let num_points = (GRID_SIZE * GRID_SIZE) as f64;
+    (
+        matching as f64 / num_points,
+        matching_rounded as f64 / num_points,
+    )
+}
+
+#[test]
+fn bla_matches_full_iteration_on_deep_zoom() {
+    let (matching, _) = compare_escape_times((-1.768778833, -0.001738996), 1e-30, 10000);
+    assert!(matching > 0.99, "only {matching} of the escape times match");
+}
+
+#[test]
+fn bla_matches_full_iteration_inside_the_set() {
+    // Points of the main cardioid never escape, and must not be made to
+    let (matching, _) = compare_escape_times((-0.1, 0.1), 1e-10, 5000);
+    assert_eq!(matching, 1.0);
+}
+
+#[test]
+fn bla_is_as_accurate_as_f32_in_seahorse_valley() {
+    // Escape times near the boundary are chaotic and any rounding changes
+    // some of them, the approximation must not do much worse than f32
+    let (matching, matching_rounded) =
+        compare_escape_times((-0.743643887037151, 0.131825904205330), 1e-10, 5000);
+    assert!(
+        1.0 - matching < 2.0 * (1.0 - matching_rounded),
+        "only {matching} of the escape times match, against {matching_rounded} with f32"
+    );
+}
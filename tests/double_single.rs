@@ -0,0 +1,113 @@
+use faraday_art::{
+    FloatChoice, to_f64,
+    utils::double_single::{
+        DoubleSingle, ds_add, ds_mul, ds_sub, join_double_single, split_double_single,
+    },
+};
+
+/// Number of random operands checked for each operation.
+const SAMPLES: usize = 10_000;
+/// Largest error relative to the exact result, in units of 2^-44, which is
+/// about 16 ulps of the 48 bits of a double-single number.
+const RELATIVE_TOLERANCE: f64 = 1.0 / (1u64 << 44) as f64;
+
+/// Splits an `f64` into the nearest double-single number.
+fn to_double_single(value: f64) -> DoubleSingle {
+    let hi = value as f32;
+    [hi, (value - hi as f64) as f32]
+}
+
+/// Returns the value of a double-single number as an `f64`, which holds it
+/// exactly.
+fn to_f64_exact(value: DoubleSingle) -> f64 {
+    value[0] as f64 + value[1] as f64
+}
+
+/// Returns random values spread over several orders of magnitude, with both
+/// signs, from a fixed seed.
+fn random_values(seed: u64) -> impl Iterator<Item = f64> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        // Xorshift generator, enough to spread the operands
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let mantissa = (state >> 11) as f64 / (1u64 << 53) as f64;
+        let exponent = (state % 16) as i32 - 8;
+        let sign = if state & (1 << 10) == 0 { 1.0 } else { -1.0 };
+        sign * (1.0 + mantissa) * 2.0_f64.powi(exponent)
+    })
+}
+
+/// Asserts that `result` matches `exact` up to [`RELATIVE_TOLERANCE`] of
+/// `scale`.
+fn assert_close(result: DoubleSingle, exact: f64, scale: f64, operation: &str) {
+    let error = (to_f64_exact(result) - exact).abs();
+    assert!(
+        error <= RELATIVE_TOLERANCE * scale,
+        "{operation}: {result:?} is {error:e} away from {exact:e}"
+    );
+}
+
+#[test]
+fn split_and_join_round_trip() {
+    for value in random_values(1).take(SAMPLES) {
+        let value = value as FloatChoice;
+        let (hi, lo) = split_double_single(value);
+        let joined = join_double_single(hi, lo);
+        let error = (to_f64(joined) - to_f64(value)).abs();
+        assert!(
+            error <= RELATIVE_TOLERANCE * to_f64(value).abs(),
+            "{value:e} was joined back to {joined:e}"
+        );
+
+        // The low part is at most half an ulp of the high part
+        let ulp = to_f64(hi as FloatChoice).abs() * f32::EPSILON as f64;
+        assert!(
+            (lo as f64).abs() <= 0.5 * ulp,
+            "{lo:e} is too large for {hi:e}"
+        );
+    }
+}
+
+#[test]
+fn split_keeps_the_nearest_shader_float() {
+    let (hi, lo) = split_double_single(0.1);
+    assert_eq!(hi, 0.1);
+    if cfg!(feature = "double-single") {
+        assert_ne!(lo, 0.0);
+    } else {
+        assert_eq!(lo, 0.0);
+    }
+}
+
+#[test]
+fn addition_matches_f64() {
+    let values: Vec<_> = random_values(2).take(2 * SAMPLES).collect();
+    for pair in values.chunks(2) {
+        let (a, b) = (to_double_single(pair[0]), to_double_single(pair[1]));
+        let (a_exact, b_exact) = (to_f64_exact(a), to_f64_exact(b));
+        let scale = a_exact.abs().max(b_exact.abs());
+        assert_close(ds_add(a, b), a_exact + b_exact, scale, "ds_add");
+        assert_close(ds_sub(a, b), a_exact - b_exact, scale, "ds_sub");
+    }
+}
+
+#[test]
+fn multiplication_matches_f64() {
+    let values: Vec<_> = random_values(3).take(2 * SAMPLES).collect();
+    for pair in values.chunks(2) {
+        let (a, b) = (to_double_single(pair[0]), to_double_single(pair[1]));
+        let exact = to_f64_exact(a) * to_f64_exact(b);
+        assert_close(ds_mul(a, b), exact, exact.abs(), "ds_mul");
+    }
+}
+
+#[test]
+fn double_single_is_more_precise_than_f32() {
+    // 1 + 2^-30 is lost by f32 but kept by double-single numbers
+    let small = 2.0_f64.powi(-30);
+    let sum = ds_add(to_double_single(1.0), to_double_single(small));
+    assert_eq!(to_f64_exact(sum), 1.0 + small);
+    assert_eq!(1.0_f32 + small as f32, 1.0);
+}
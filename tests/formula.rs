@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{E, PI},
+};
+
+use faraday_art::utils::{
+    formula::{Formula, FormulaKind, FormulaSet},
+    pipeline::GPUPipeline,
+};
+use num::complex::Complex64;
+
+/// Largest difference accepted between the evaluated and expected values.
+const TOLERANCE: f64 = 1e-12;
+
+/// Parses a formula, panicking with its error if it is invalid.
+fn parse(kind: FormulaKind, source: &str) -> Formula {
+    Formula::parse(kind, source).unwrap_or_else(|error| panic!("{source}: {error}"))
+}
+
+/// Evaluates a formula with real variables.
+fn evaluate_real(kind: FormulaKind, source: &str, variables: &[f64]) -> Vec<f64> {
+    let variables: Vec<_> = variables.iter().map(|x| Complex64::new(*x, 0.0)).collect();
+    parse(kind, source)
+        .evaluate(&variables)
+        .into_iter()
+        .map(|value| {
+            assert_eq!(value.im, 0.0, "{source} has a complex output");
+            value.re
+        })
+        .collect()
+}
+
+/// Asserts that two values are equal up to [`TOLERANCE`], relative to their
+/// magnitude.
+fn assert_close(value: Complex64, expected: Complex64, source: &str) {
+    let scale = expected.norm().max(1.0);
+    assert!(
+        (value - expected).norm() <= TOLERANCE * scale,
+        "{source} evaluated to {value} instead of {expected}"
+    );
+}
+
+/// Returns the error of an invalid formula as its column and message.
+fn parse_error(kind: FormulaKind, source: &str) -> (usize, String) {
+    let error = Formula::parse(kind, source).expect_err(source);
+    (error.column(), error.message().to_string())
+}
+
+/// Returns the body of the WGSL function of a formula, without the comment
+/// quoting its source.
+fn wgsl_body(kind: FormulaKind, source: &str) -> String {
+    let wgsl = parse(kind, source).to_wgsl();
+    wgsl.lines().skip(1).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn escape_time_formulas_match_num() {
+    let source = "z^3 + c*sin(z)";
+    let formula = parse(FormulaKind::EscapeTime, source);
+    for (z, c) in [
+        (Complex64::new(0.3, -0.2), Complex64::new(-0.5, 0.1)),
+        (Complex64::new(-1.2, 0.7), Complex64::new(0.25, 0.0)),
+    ] {
+        let value = formula.evaluate(&[z, c]);
+        assert_eq!(value.len(), 1);
+        assert_close(value[0], z.powi(3) + c * z.sin(), source);
+    }
+}
+
+#[test]
+fn flow_formulas_assign_both_derivatives() {
+    let source = "dx = y; dy = mu*(1-x^2)*y - x";
+    let (x, y, mu) = (0.5, -1.5, 2.0);
+    let value = evaluate_real(FormulaKind::Flow, source, &[x, y, mu]);
+    assert_eq!(value, [y, mu * (1.0 - x * x) * y - x]);
+
+    // The assignments can come in any order
+    let swapped = evaluate_real(FormulaKind::Flow, "dy = -x; dx = y;", &[x, y, mu]);
+    assert_eq!(swapped, [y, -x]);
+}
+
+#[test]
+fn function_formulas_support_implicit_multiplication() {
+    let source = "y = -x*cos(exp(sin(10x))*x)";
+    for x in [-2.0, 0.0, 0.7, 3.1] {
+        let value = evaluate_real(FormulaKind::Function, source, &[x]);
+        assert_eq!(value, [-x * ((10.0 * x).sin().exp() * x).cos()]);
+    }
+
+    // Implicit multiplication generates the same code as the explicit one
+    for (implicit, explicit) in [
+        ("y = sin(10x)", "y = sin(10*x)"),
+        ("y = 2(x + 1)", "y = 2*(x + 1)"),
+        ("y = 2pi x", "y = 2*pi*x"),
+        ("y = (x + 1)(x - 1)", "y = (x + 1)*(x - 1)"),
+    ] {
+        assert_eq!(
+            wgsl_body(FormulaKind::Function, implicit),
+            wgsl_body(FormulaKind::Function, explicit)
+        );
+    }
+}
+
+#[test]
+fn unary_minus_binds_looser_than_powers() {
+    for (source, x, expected) in [
+        ("y = -x^2", 3.0, -9.0),
+        ("y = (-x)^2", 3.0, 9.0),
+        ("y = -2^2", 0.0, -4.0),
+        ("y = 2^-1", 0.0, 0.5),
+        ("y = 2^-x", 2.0, 0.25),
+        ("y = 2^-x^2", 2.0, 1.0 / 16.0),
+        ("y = 2^3^2", 0.0, 512.0),
+        ("y = x^2x", 3.0, 27.0),
+        ("y = 1 - -x", 3.0, 4.0),
+    ] {
+        assert_eq!(
+            evaluate_real(FormulaKind::Function, source, &[x]),
+            [expected],
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn unknown_identifiers_are_reported_at_their_column() {
+    assert_eq!(
+        parse_error(FormulaKind::EscapeTime, "z^2 + q"),
+        (
+            7,
+            "unknown variable or function q, the variables are z, c".to_string()
+        )
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Function, "y = sinus(x)"),
+        (
+            5,
+            "unknown variable or function sinus, the variables are x".to_string()
+        )
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Flow, "dx = y; dz = x"),
+        (9, "cannot assign dz, expected dx and dy".to_string())
+    );
+}
+
+#[test]
+fn complex_values_of_real_outputs_are_reported() {
+    assert_eq!(
+        parse_error(FormulaKind::Function, "y = x + i"),
+        (
+            1,
+            "y must be real, but its expression is complex".to_string()
+        )
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Flow, "dx = y; dy = x*i"),
+        (
+            9,
+            "dy must be real, but its expression is complex".to_string()
+        )
+    );
+
+    // Real functions of complex values are real
+    parse(FormulaKind::Function, "y = abs(x + i) + re(exp(i*x))");
+}
+
+#[test]
+fn malformed_numbers_are_reported_at_their_column() {
+    assert_eq!(
+        parse_error(FormulaKind::EscapeTime, "z^2 + 1.2.3"),
+        (7, "invalid number 1.2.3".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Function, "y = 1e40 * x"),
+        (5, "invalid number 1e40".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Function, "y = x $ 2"),
+        (7, "unexpected character '$'".to_string())
+    );
+}
+
+#[test]
+fn incomplete_formulas_are_reported() {
+    assert_eq!(
+        parse_error(FormulaKind::EscapeTime, "z^2 +"),
+        (6, "unexpected end of the formula".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::EscapeTime, "sin(z"),
+        (6, "expected ')'".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Flow, "dx = y"),
+        (7, "dy is not assigned".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::Flow, "dx = y; dx = x"),
+        (9, "dx is assigned twice".to_string())
+    );
+    assert_eq!(
+        parse_error(FormulaKind::EscapeTime, ""),
+        (1, "the formula is empty".to_string())
+    );
+}
+
+#[test]
+fn complex_functions_match_num() {
+    let source = "exp(z)*log(c) + sqrt(z)/conj(c) - tan(z) + cosh(c)^2 + z^c \
+                  + sinh(z) - tanh(c) + cos(z)/(2 + i) - sin(c) + z^0.5";
+    let formula = parse(FormulaKind::EscapeTime, source);
+    let (z, c) = (Complex64::new(0.4, -0.3), Complex64::new(-0.7, 0.2));
+    let expected =
+        z.exp() * c.ln() + z.sqrt() / c.conj() - z.tan() + c.cosh().powi(2) + z.powc(c) + z.sinh()
+            - c.tanh()
+            + z.cos() / Complex64::new(2.0, 1.0)
+            - c.sin()
+            + z.powf(0.5);
+    assert_close(formula.evaluate(&[z, c])[0], expected, source);
+
+    let source = "abs(z) + i*arg(z) + re(c)*im(c) + pi*e";
+    let formula = parse(FormulaKind::EscapeTime, source);
+    let expected = z.norm() + Complex64::i() * z.arg() + c.re * c.im + PI * E;
+    assert_close(formula.evaluate(&[z, c])[0], expected, source);
+}
+
+#[test]
+fn real_functions_match_std() {
+    let source = "dx = abs(x - 2) + arg(-y) + log(mu)*sqrt(y); \
+                  dy = sinh(x)/cosh(y) + tanh(mu) - tan(x) + re(x) + im(y) + conj(mu)";
+    let (x, y, mu) = (0.5, 1.5, 2.0);
+    let value = evaluate_real(FormulaKind::Flow, source, &[x, y, mu]);
+    let expected = [
+        (x - 2.0).abs() + PI + mu.ln() * y.sqrt(),
+        x.sinh() / y.cosh() + mu.tanh() - x.tan() + x + mu,
+    ];
+    for (value, expected) in value.into_iter().zip(expected) {
+        assert_close(value.into(), expected.into(), source);
+    }
+}
+
+#[test]
+fn integer_powers_of_negative_values_are_defined() {
+    // The constant exponents are folded into integer powers on the GPU too
+    for source in ["y = x^2", "y = x^(1+1)", "y = x^(4/2)", "y = x^sqrt(4)"] {
+        assert_eq!(
+            evaluate_real(FormulaKind::Function, source, &[-3.0]),
+            [9.0],
+            "{source}"
+        );
+        assert_eq!(
+            wgsl_body(FormulaKind::Function, source),
+            "fn formula_function(x: f32) -> f32 {\n    return rx_powi(x, 2);\n}",
+            "{source}"
+        );
+    }
+    assert_eq!(
+        evaluate_real(FormulaKind::Function, "y = x^-(1+2)", &[-2.0]),
+        [-0.125]
+    );
+
+    // Other exponents are only known on the GPU, whose rx_pow follows powf
+    let value = evaluate_real(FormulaKind::Flow, "dx = x^y; dy = 0", &[-2.0, 3.0, 0.0]);
+    assert_eq!(value, [-8.0, 0.0]);
+    assert!(wgsl_body(FormulaKind::Flow, "dx = x^y; dy = 0").contains("rx_pow(x, y)"));
+    assert!(wgsl_body(FormulaKind::Function, "y = x^(pi/2)").contains("rx_pow(x, "));
+}
+
+#[test]
+fn generated_wgsl_is_valid() {
+    let mut formulas = FormulaSet::default();
+    for (kind, source) in [
+        (
+            FormulaKind::EscapeTime,
+            "z^3 + c*sin(z) - cos(z)/c + tan(z)*sinh(c) - cosh(z)/tanh(c) + exp(z) \
+             - log(c) + sqrt(z) + conj(c) + z^c + z^1.5 + 2^z + z^-2 \
+             + abs(z) + arg(z) + re(c) - im(c) + pi*e*i",
+        ),
+        (
+            FormulaKind::Flow,
+            "dx = y; dy = mu*(1-x^2)*y - x + x^y + abs(x) + arg(y) + re(x) + im(y) \
+             + conj(mu) + sin(x)*cos(y)/tan(mu) + sinh(x) - cosh(y) + tanh(mu) \
+             + exp(x) + log(y) + sqrt(mu) + abs(x + i*y)",
+        ),
+        (FormulaKind::Function, "y = -x*cos(exp(sin(10x))*x)"),
+    ] {
+        formulas.set(parse(kind, source));
+    }
+
+    let sources: HashMap<String, String> = GPUPipeline::SHADER_FILES
+        .iter()
+        .map(|(name, source)| (name.to_string(), source.to_string()))
+        .collect();
+    for shader_f64 in [false, true] {
+        if let Err(error) =
+            GPUPipeline::preprocess_shader(&sources, &formulas, "compute.wgsl", shader_f64)
+        {
+            panic!("{error}");
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use faraday_art::utils::integrator::Integrator;
+
+/// Time at which the numerical solutions are compared to the exact ones.
+const END_TIME: f64 = 2.0;
+/// Initial point of the trajectories.
+const START: [f64; 2] = [1.0, 0.5];
+
+/// Harmonic oscillator x' = y, y' = -x, which is separable.
+fn oscillator(z: [f64; 2]) -> [f64; 2] {
+    [z[1], -z[0]]
+}
+
+/// Exact solution of [`oscillator`] at time t, a rotation of the start.
+fn oscillator_solution(t: f64) -> [f64; 2] {
+    let (sin, cos) = t.sin_cos();
+    [
+        cos * START[0] + sin * START[1],
+        -sin * START[0] + cos * START[1],
+    ]
+}
+
+/// Damped spiral z' = (-0.5 + i) z, which is not separable.
+fn spiral(z: [f64; 2]) -> [f64; 2] {
+    [-0.5 * z[0] - z[1], z[0] - 0.5 * z[1]]
+}
+
+/// Exact solution of [`spiral`] at time t.
+fn spiral_solution(t: f64) -> [f64; 2] {
+    let (sin, cos) = t.sin_cos();
+    let decay = (-0.5 * t).exp();
+    [
+        decay * (cos * START[0] - sin * START[1]),
+        decay * (sin * START[0] + cos * START[1]),
+    ]
+}
+
+/// Returns the distance to the exact solution at [`END_TIME`] after
+/// integrating `steps` fixed steps.
+fn global_error(
+    integrator: Integrator,
+    field: fn([f64; 2]) -> [f64; 2],
+    solution: fn(f64) -> [f64; 2],
+    steps: u32,
+) -> f64 {
+    let dt = END_TIME / steps as f64;
+    let mut z = START;
+    for _ in 0..steps {
+        z = integrator.step(field, z, dt);
+    }
+    let exact = solution(END_TIME);
+    (z[0] - exact[0]).hypot(z[1] - exact[1])
+}
+
+/// Estimates the order of convergence of an integrator from the errors with
+/// `steps` and twice as many steps.
+fn convergence_order(
+    integrator: Integrator,
+    field: fn([f64; 2]) -> [f64; 2],
+    solution: fn(f64) -> [f64; 2],
+    steps: u32,
+) -> f64 {
+    let coarse = global_error(integrator, field, solution, steps);
+    let fine = global_error(integrator, field, solution, 2 * steps);
+    (coarse / fine).log2()
+}
+
+/// Asserts that the estimated order of convergence of an integrator matches
+/// [`Integrator::order`].
+fn assert_order(integrator: Integrator, order: f64) {
+    let expected = integrator.order() as f64;
+    assert!(
+        (order - expected).abs() < 0.2,
+        "{integrator:?} converges with order {order:.2} instead of {expected}"
+    );
+}
+
+#[test]
+fn integrators_converge_with_their_order_on_the_oscillator() {
+    for integrator in Integrator::ALL {
+        let order = convergence_order(integrator, oscillator, oscillator_solution, 20);
+        assert_order(integrator, order);
+    }
+}
+
+#[test]
+fn runge_kutta_integrators_converge_with_their_order_on_the_spiral() {
+    // The leapfrog method is only symplectic for separable systems
+    for integrator in Integrator::ALL {
+        if integrator != Integrator::Leapfrog {
+            let order = convergence_order(integrator, spiral, spiral_solution, 20);
+            assert_order(integrator, order);
+        }
+    }
+}
+
+#[test]
+fn leapfrog_conserves_the_energy_of_the_oscillator() {
+    let energy = |z: [f64; 2]| 0.5 * (z[0] * z[0] + z[1] * z[1]);
+    let mut z = START;
+    let mut max_drift: f64 = 0.0;
+    for _ in 0..100_000 {
+        z = Integrator::Leapfrog.step(oscillator, z, 0.1);
+        max_drift = max_drift.max((energy(z) - energy(START)).abs());
+    }
+    // The energy oscillates by O(dt^2) without drifting, unlike RK4 whose
+    // energy decays steadily over as many steps
+    assert!(
+        max_drift < 0.01 * energy(START),
+        "energy drifted by {max_drift}"
+    );
+}
+
+#[test]
+fn adaptive_integrator_meets_the_tolerance() {
+    let mut previous_error = f64::INFINITY;
+    for tolerance in [1e-3, 1e-5, 1e-7, 1e-9] {
+        let mut z = START;
+        let mut t = 0.0;
+        let mut dt: f64 = 0.5;
+        let mut steps = 0;
+        while t < END_TIME {
+            let (next, taken, next_dt) =
+                Integrator::DormandPrince.adaptive_step(spiral, z, dt.min(END_TIME - t), tolerance);
+            z = next;
+            t += taken;
+            dt = next_dt;
+            steps += 1;
+        }
+
+        let exact = spiral_solution(END_TIME);
+        let error = (z[0] - exact[0]).hypot(z[1] - exact[1]);
+        assert!(
+            error < 10.0 * tolerance,
+            "error {error} with tolerance {tolerance} after {steps} steps"
+        );
+        assert!(error < previous_error);
+        previous_error = error;
+    }
+}
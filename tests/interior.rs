@@ -0,0 +1,82 @@
+use faraday_art::{
+    ShaderFloat,
+    utils::interior::{InteriorCheck, mandelbrot_iterations},
+};
+
+/// Number of pixels per side of the grid of points compared.
+const GRID_SIZE: usize = 200;
+
+/// Compares the escape times of a grid of points of the Mandelbrot set,
+/// iterated with and without the interior checks.
+///
+/// # Returns
+///
+/// - The number of points whose iteration stopped early, for each check of
+///   [`InteriorCheck::ALL`].
+///
+/// # Panics
+///
+/// Panics if the checks change the escape time of any point.
+fn compare_escape_times(center: (f64, f64), size: f64, max_iter: u32) -> [usize; 3] {
+    let pixel_size = (size / GRID_SIZE as f64) as ShaderFloat;
+    let mut early_exits = [0; 3];
+    for i in 0..GRID_SIZE {
+        for j in 0..GRID_SIZE {
+            let c = [
+                (center.0 + (i as f64 / GRID_SIZE as f64 - 0.5) * size) as ShaderFloat,
+                (center.1 + (j as f64 / GRID_SIZE as f64 - 0.5) * size) as ShaderFloat,
+            ];
+            let (full, _) = mandelbrot_iterations(c, max_iter, 2.0, pixel_size, 0);
+            let (checked, check) =
+                mandelbrot_iterations(c, max_iter, 2.0, pixel_size, InteriorCheck::ALL_MASK);
+            assert_eq!(full, checked, "escape time changed at {c:?} by {check:?}");
+
+            if let Some(check) = check {
+                early_exits[check.index()] += 1;
+            }
+        }
+    }
+
+    early_exits
+}
+
+#[test]
+fn interior_checks_keep_the_classification_of_the_whole_set() {
+    let early_exits = compare_escape_times((-0.75, 0.0), 2.5, 1000);
+    for check in InteriorCheck::ALL {
+        assert!(
+            early_exits[check.index()] > 0,
+            "{check:?} never exited early"
+        );
+    }
+}
+
+#[test]
+fn interior_checks_keep_the_classification_of_the_cardioid_cusp() {
+    // Orbits near the cusp are nearly periodic for many iterations before
+    // escaping
+    compare_escape_times((0.25, 0.0), 0.01, 2000);
+}
+
+#[test]
+fn interior_checks_keep_the_classification_of_seahorse_valley() {
+    let early_exits = compare_escape_times((-0.745, 0.11), 0.01, 2000);
+    assert!(early_exits[InteriorCheck::Periodicity.index()] > 0);
+}
+
+#[test]
+fn interior_checks_keep_the_classification_between_the_cardioid_and_the_bulb() {
+    compare_escape_times((-0.75, 0.0), 0.001, 2000);
+}
+
+#[test]
+fn all_mask_enables_each_check_at_its_index() {
+    for (index, check) in InteriorCheck::ALL.into_iter().enumerate() {
+        assert!(check.is_enabled(InteriorCheck::ALL_MASK), "{check:?}");
+        assert_eq!(check.index(), index, "{check:?}");
+    }
+    assert_eq!(
+        InteriorCheck::ALL_MASK.count_ones() as usize,
+        InteriorCheck::ALL.len()
+    );
+}
@@ -0,0 +1,60 @@
+use faraday_art::utils::{
+    integrator::Integrator,
+    limit_cycle::{van_der_pol_cycle, van_der_pol_field},
+};
+
+/// Number of points sampled along the cycles.
+const LEN: usize = 256;
+
+#[test]
+fn limit_cycle_matches_the_known_period_and_amplitude() {
+    // Reference values for mu = 1
+    let cycle = van_der_pol_cycle(1.0, LEN);
+    assert!(
+        (cycle.period - 6.663_286_859).abs() < 1e-3,
+        "period {}",
+        cycle.period
+    );
+
+    // The cycle starts where x is largest
+    let amplitude = cycle.points[0][0];
+    assert!(
+        (amplitude - 2.008_620).abs() < 1e-3,
+        "amplitude {amplitude}"
+    );
+    assert!(cycle.points[0][1].abs() < 1e-3);
+    assert!(
+        cycle
+            .points
+            .iter()
+            .all(|point| point[0] <= amplitude + 1e-3)
+    );
+}
+
+#[test]
+fn limit_cycle_tends_to_a_circle_for_small_mu() {
+    let cycle = van_der_pol_cycle(0.01, LEN);
+    assert!((cycle.period - std::f64::consts::TAU).abs() < 1e-3);
+    for point in &cycle.points {
+        let radius = point[0].hypot(point[1]);
+        assert!((radius - 2.0).abs() < 0.02, "radius {radius}");
+    }
+}
+
+#[test]
+fn limit_cycle_points_are_evenly_spaced_in_time() {
+    // Consecutive points are a constant fraction of the period apart, so the
+    // last point leads back to the first one
+    let mu = 4.5;
+    let cycle = van_der_pol_cycle(mu, LEN);
+    let field = van_der_pol_field(mu);
+    let substeps = 100;
+    let dt = cycle.period / (LEN * substeps) as f64;
+    let mut z = cycle.points[LEN - 1];
+    for _ in 0..substeps {
+        z = Integrator::Rk4.step(&field, z, dt);
+    }
+    let first = cycle.points[0];
+    let gap = (first[0] - z[0]).hypot(first[1] - z[1]);
+    assert!(gap < 1e-3, "gap {gap}");
+}
@@ -0,0 +1,20 @@
+use faraday_art::utils::orbit_trap::fit_trap_image;
+use nannou::image::{Rgba, RgbaImage};
+
+#[test]
+fn images_within_the_texture_limit_are_kept() {
+    let image = RgbaImage::new(64, 32);
+    assert!(fit_trap_image(&image, 64).is_none());
+}
+
+#[test]
+fn large_images_are_downscaled_to_the_texture_limit() {
+    let image = RgbaImage::from_pixel(400, 100, Rgba([255, 0, 0, 255]));
+    let fitted = fit_trap_image(&image, 64).unwrap();
+    assert_eq!(fitted.dimensions(), (64, 16));
+    assert_eq!(*fitted.get_pixel(10, 10), Rgba([255, 0, 0, 255]));
+
+    // The shorter side keeps at least one pixel
+    let image = RgbaImage::new(10, 1000);
+    assert_eq!(fit_trap_image(&image, 64).unwrap().dimensions(), (1, 64));
+}
@@ -0,0 +1,113 @@
+use faraday_art::utils::perturbation::{DeepZoomView, escape_iterations, escape_iterations_scaled};
+
+/// Number of pixels per side of the grid of points compared.
+const GRID_SIZE: usize = 32;
+
+/// Escape radius large enough for the reference to escape decisively, so that
+/// its last point does not round to the bailout in `f32`.
+const BAILOUT: f64 = 100.0;
+
+/// Returns a view of `size` centered on `center`, which may be given with more
+/// precision than `f64` by an offset relative to the size.
+fn deep_view(center: (f64, f64), size: f64, offset: f64) -> DeepZoomView {
+    // Start from a shallow view, as the ranges of deep views cannot be
+    // represented with f64
+    let mut view = DeepZoomView::from_ranges(
+        (center.0 - 0.5, center.0 + 0.5),
+        (center.1 - 0.5, center.1 + 0.5),
+    );
+    view.zoom_relative(size, (0.5, 0.5));
+    view.shift_relative((offset, 0.0));
+    view
+}
+
+/// Returns a view of `size` centered on the nucleus of the real minibrot of
+/// the given period closest to the tip at -2, found by bisection.
+fn minibrot_view(period: u32, size: f64) -> DeepZoomView {
+    // The orbit at -2 stays at 2, while Z_period changes sign once the offset
+    // exceeds about 12 / 4^period
+    let offset = 16.0 * 4f64.powi(-(period as i32));
+    let mut view = deep_view((-2.0, 0.0), offset, 0.5);
+    while view.get_size().0 > size / GRID_SIZE as f64 {
+        let z = view.reference_orbit(period, BAILOUT)[period as usize];
+        let toward_tip = z[0].is_sign_negative();
+        view.shift_relative((if toward_tip { -0.25 } else { 0.25 }, 0.0));
+        view.zoom_relative(0.5, (0.5, 0.5));
+    }
+    while view.get_size().0 < size {
+        view.zoom_relative(2.0, (0.5, 0.5));
+    }
+    view
+}
+
+/// Compares the escape times of a grid of points around the center of `view`,
+/// iterated with `f64` deltas and with the scaled deltas of the shader.
+///
+/// # Returns
+///
+/// - The number of points with the same escape time with both deltas.
+/// - The number of points iterated past the end of the reference orbit.
+/// - The number of points that do not escape.
+fn compare_scaled_escape_times(view: &DeepZoomView, max_iter: u32) -> (usize, usize, usize) {
+    let reference_orbit = view.reference_orbit(max_iter, BAILOUT);
+    let (span, exponent) = view.get_scaled_size();
+
+    let (mut matching, mut rebased, mut inside) = (0, 0, 0);
+    for i in 0..GRID_SIZE {
+        for j in 0..GRID_SIZE {
+            let dc = [
+                (i as f32 / (GRID_SIZE - 1) as f32 - 0.5) * span[0],
+                (j as f32 / (GRID_SIZE - 1) as f32 - 0.5) * span[1],
+            ];
+            let unscaled = (
+                dc[0] as f64 * 2f64.powi(exponent),
+                dc[1] as f64 * 2f64.powi(exponent),
+            );
+            let full = escape_iterations(&reference_orbit, unscaled, max_iter, BAILOUT, None);
+            let scaled =
+                escape_iterations_scaled(&reference_orbit, dc, exponent, max_iter, BAILOUT, None);
+            if full == scaled {
+                matching += 1;
+            }
+            if full as usize >= reference_orbit.len() {
+                rebased += 1;
+            }
+            if full == max_iter {
+                inside += 1;
+            }
+        }
+    }
+    (matching, rebased, inside)
+}
+
+#[test]
+fn scaled_deltas_match_f64_deltas() {
+    let view = deep_view((-1.768778833, -0.001738996), 1e-30, 0.0);
+    let (matching, _, _) = compare_scaled_escape_times(&view, 5000);
+    assert_eq!(matching, GRID_SIZE * GRID_SIZE);
+}
+
+#[test]
+fn scaled_deltas_rebase_past_an_escaped_reference_below_f32_range() {
+    // Deltas of these views are far below the smallest normal f32, so the
+    // rebase must not go through a saturated power of two. The reference is
+    // moved off-center so that half of the view is past it.
+    for size in [1e-45, 1e-100] {
+        let view = deep_view((-2.0, 0.0), size, -0.25);
+        assert!(view.reference_orbit(5000, BAILOUT).len() < 5000);
+
+        let (matching, rebased, _) = compare_scaled_escape_times(&view, 5000);
+        assert!(rebased > 0, "no point outlived the reference at {size}");
+        assert_eq!(matching, GRID_SIZE * GRID_SIZE, "mismatch at {size}");
+    }
+}
+
+#[test]
+fn scaled_deltas_resolve_a_minibrot_below_f32_range() {
+    // The reference passes through 0 at the nucleus, where dz^2 and dc are
+    // much smaller than dz and must not be flushed to 0
+    let view = minibrot_view(80, 1e-94);
+    let (matching, _, inside) = compare_scaled_escape_times(&view, 1600);
+    assert!(inside > 0 && inside < GRID_SIZE * GRID_SIZE);
+    assert_eq!(matching, GRID_SIZE * GRID_SIZE);
+}
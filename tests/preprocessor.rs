@@ -0,0 +1,200 @@
+use faraday_art::utils::preprocessor::{Preprocessor, SourceLine};
+
+/// Preprocesses `source` and returns its non-empty lines, trimmed.
+fn process_lines(preprocessor: &Preprocessor, source: &str) -> Vec<String> {
+    preprocessor
+        .process("test.wgsl", source)
+        .unwrap()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn defines_replace_whole_identifiers_outside_comments() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("SIZE", "16u");
+    let source = "let a = SIZE + MAX_SIZE + SIZE_2; // SIZE\n";
+    assert_eq!(
+        process_lines(&preprocessor, source),
+        ["let a = 16u + MAX_SIZE + SIZE_2; // SIZE"]
+    );
+}
+
+#[test]
+fn shaders_can_define_constants() {
+    let preprocessor = Preprocessor::new();
+    let source = "let a = N;\n#define N 2.0 * PI\n#define PI 3.14\nlet b = N;\n";
+    assert_eq!(
+        process_lines(&preprocessor, source),
+        ["let a = N;", "let b = 2.0 * PI;"]
+    );
+}
+
+#[test]
+fn conditionals_follow_defines() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("F64", "");
+    let source = "\
+#ifdef F64
+a
+#else
+b
+#endif
+#ifndef F64
+c
+#else
+d
+#endif
+";
+    assert_eq!(process_lines(&preprocessor, source), ["a", "d"]);
+    assert_eq!(process_lines(&Preprocessor::new(), source), ["b", "c"]);
+}
+
+#[test]
+fn nested_conditionals_need_their_parent() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("INNER", "");
+    let source = "\
+#ifdef OUTER
+a
+#ifdef INNER
+b
+#else
+c
+#endif
+#else
+d
+#ifndef INNER
+e
+#else
+f
+#endif
+#endif
+";
+    assert_eq!(process_lines(&preprocessor, source), ["d", "f"]);
+
+    preprocessor.define("OUTER", "");
+    assert_eq!(process_lines(&preprocessor, source), ["a", "b"]);
+}
+
+#[test]
+fn inactive_blocks_do_not_define_nor_include() {
+    let preprocessor = Preprocessor::new();
+    let source = "\
+#ifdef MISSING
+#define A 1
+#include \"missing.wgsl\"
+#endif
+A
+";
+    assert_eq!(process_lines(&preprocessor, source), ["A"]);
+}
+
+#[test]
+fn includes_are_preprocessed_in_place() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.define("VALUE", "1.0");
+    preprocessor.add_include("constants.wgsl", "#define HALF 0.5\nconst A = VALUE;");
+    let source = "#include \"constants.wgsl\"\nconst B = HALF;\n";
+    assert_eq!(
+        process_lines(&preprocessor, source),
+        ["const A = 1.0;", "const B = 0.5;"]
+    );
+}
+
+#[test]
+fn directives_keep_the_line_numbers() {
+    let preprocessor = Preprocessor::new();
+    let source = "#define A 1\n#ifdef B\nskipped\n#endif\nA\n";
+    let output = preprocessor.process("test.wgsl", source).unwrap();
+    assert_eq!(output.lines().collect::<Vec<_>>(), ["", "", "", "", "1"]);
+}
+
+#[test]
+fn output_lines_are_mapped_to_their_file() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.add_include("included.wgsl", "b\nc");
+    let (output, lines) = preprocessor
+        .process_mapped("test.wgsl", "a\n#include \"included.wgsl\"\nd\n")
+        .unwrap();
+    let origin = |file: &str, line| SourceLine {
+        file: file.to_string(),
+        line,
+    };
+    assert_eq!(output.lines().collect::<Vec<_>>(), ["a", "b", "c", "", "d"]);
+    assert_eq!(
+        lines,
+        [
+            origin("test.wgsl", 1),
+            origin("included.wgsl", 1),
+            origin("included.wgsl", 2),
+            origin("test.wgsl", 2),
+            origin("test.wgsl", 3),
+        ]
+    );
+}
+
+#[test]
+fn unterminated_conditionals_are_errors() {
+    let error = Preprocessor::new()
+        .process("test.wgsl", "#ifdef A\na\n")
+        .unwrap_err();
+    assert_eq!(error, "test.wgsl: missing #endif");
+}
+
+#[test]
+fn unmatched_directives_are_errors() {
+    let preprocessor = Preprocessor::new();
+    assert_eq!(
+        preprocessor
+            .process("test.wgsl", "a\n#endif\n")
+            .unwrap_err(),
+        "test.wgsl:2: #endif without matching #ifdef"
+    );
+    assert_eq!(
+        preprocessor.process("test.wgsl", "#else\n").unwrap_err(),
+        "test.wgsl:1: #else without matching #ifdef"
+    );
+    assert_eq!(
+        preprocessor
+            .process("test.wgsl", "#ifdef A\n#else\n#else\n#endif\n")
+            .unwrap_err(),
+        "test.wgsl:3: #else without matching #ifdef"
+    );
+    assert_eq!(
+        preprocessor.process("test.wgsl", "#if A\n").unwrap_err(),
+        "test.wgsl:1: invalid directive #if A"
+    );
+}
+
+#[test]
+fn unknown_includes_are_errors() {
+    let error = Preprocessor::new()
+        .process("test.wgsl", "a\n#include \"missing.wgsl\"\n")
+        .unwrap_err();
+    assert_eq!(error, "test.wgsl:2: unknown include \"missing.wgsl\"");
+}
+
+#[test]
+fn errors_name_the_included_file() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.add_include("broken.wgsl", "a\n#ifdef A\n");
+    let error = preprocessor
+        .process("test.wgsl", "#include \"broken.wgsl\"\n")
+        .unwrap_err();
+    assert_eq!(error, "broken.wgsl: missing #endif");
+}
+
+#[test]
+fn recursive_includes_are_errors() {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.add_include("a.wgsl", "#include \"b.wgsl\"");
+    preprocessor.add_include("b.wgsl", "#include \"a.wgsl\"");
+    let error = preprocessor
+        .process("test.wgsl", "#include \"a.wgsl\"\n")
+        .unwrap_err();
+    assert!(error.ends_with("too many nested includes"), "{error}");
+}
@@ -0,0 +1,84 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use faraday_art::utils::{
+    perturbation::DeepZoomView,
+    reference::{Reference, ReferenceCache, ReferenceParams},
+};
+
+/// Returns the parameters of a reference at the center of a deep view of
+/// `size` in the seahorse valley.
+fn params(size: f64, use_bla: bool) -> ReferenceParams {
+    let mut view = DeepZoomView::from_ranges((-1.25, -0.25), (-0.4, 0.6));
+    view.zoom_relative(size, (0.5, 0.5));
+    ReferenceParams {
+        view,
+        max_iter: 1000,
+        bailout: 2.0,
+        use_bla,
+    }
+}
+
+/// Polls the cache until a reference is built, failing after a few seconds.
+fn wait_for_reference(cache: &mut ReferenceCache) -> Reference {
+    let start = Instant::now();
+    loop {
+        if let Some(reference) = cache.poll() {
+            return reference;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "no reference built"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn references_are_built_in_the_background() {
+    let mut cache = ReferenceCache::new();
+    assert!(!cache.is_built());
+
+    let params = params(1e-20, true);
+    cache.request(params.clone());
+    let reference = wait_for_reference(&mut cache);
+    assert!(cache.is_built());
+    assert_eq!(reference.params, params);
+    assert_eq!(reference.orbit, params.view.reference_orbit(1000, 2.0));
+    assert!(reference.bla_table.is_some());
+}
+
+#[test]
+fn unchanged_references_are_not_rebuilt() {
+    let mut cache = ReferenceCache::new();
+    cache.request(params(1e-20, false));
+    let reference = wait_for_reference(&mut cache);
+    assert!(reference.bla_table.is_none());
+
+    cache.request(params(1e-20, false));
+    assert!(!cache.is_building());
+    cache.request(params(1e-20, true));
+    assert!(cache.is_building());
+}
+
+#[test]
+fn only_the_latest_queued_reference_is_built() {
+    let mut cache = ReferenceCache::new();
+    cache.request(params(1e-20, false));
+    cache.request(params(1e-21, false));
+    cache.request(params(1e-22, false));
+
+    // The reference being built is still returned, then the latest one
+    assert_eq!(wait_for_reference(&mut cache).params, params(1e-20, false));
+    assert_eq!(wait_for_reference(&mut cache).params, params(1e-22, false));
+    assert!(!cache.is_building());
+
+    // Going back to the reference being built drops the queued one
+    cache.request(params(1e-23, false));
+    cache.request(params(1e-24, false));
+    cache.request(params(1e-23, false));
+    assert_eq!(wait_for_reference(&mut cache).params, params(1e-23, false));
+    assert!(!cache.is_building());
+}
@@ -0,0 +1,112 @@
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use faraday_art::utils::{
+    formula::FormulaSet,
+    pipeline::GPUPipeline,
+    shader_watcher::{ShaderWatcher, rebuild_or_keep},
+};
+
+/// Creates an empty directory for the shaders of a test.
+fn shader_directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("faraday-art-{test}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Writes a shader file with a given modification time, since writes within
+/// the resolution of the file system would not change it.
+fn write_shader(path: &PathBuf, source: &str, modified: SystemTime) {
+    fs::write(path, source).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+#[test]
+fn watcher_reports_modified_files() {
+    let directory = shader_directory("modified");
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    write_shader(&directory.join("a.wgsl"), "a", time);
+    write_shader(&directory.join("b.wgsl"), "b", time);
+
+    // All the files are loaded first, then only the modified ones
+    let mut watcher = ShaderWatcher::new(&directory, &["a.wgsl", "b.wgsl"]);
+    let changed = watcher.changed_files().unwrap();
+    assert_eq!(
+        changed,
+        [
+            ("a.wgsl".to_string(), "a".to_string()),
+            ("b.wgsl".to_string(), "b".to_string())
+        ]
+    );
+    assert!(watcher.changed_files().unwrap().is_empty());
+
+    write_shader(
+        &directory.join("b.wgsl"),
+        "b2",
+        time + Duration::from_secs(1),
+    );
+    let changed = watcher.changed_files().unwrap();
+    assert_eq!(changed, [("b.wgsl".to_string(), "b2".to_string())]);
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn watcher_retries_unreadable_files() {
+    let directory = shader_directory("unreadable");
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    write_shader(&directory.join("a.wgsl"), "a", time);
+
+    // No file is marked as seen while one of them is missing
+    let mut watcher = ShaderWatcher::new(&directory, &["a.wgsl", "b.wgsl"]);
+    let error = watcher.changed_files().unwrap_err();
+    assert!(error.contains("b.wgsl"), "{error}");
+
+    write_shader(&directory.join("b.wgsl"), "b", time);
+    let changed = watcher.changed_files().unwrap();
+    assert_eq!(changed.len(), 2);
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn failed_reloads_keep_the_previous_shader() {
+    let formulas = FormulaSet::default();
+    let mut sources = GPUPipeline::embedded_shader_sources();
+    let mut compute =
+        GPUPipeline::preprocess_shader(&sources, &formulas, "compute.wgsl", false).unwrap();
+    let previous = compute.clone();
+
+    // A broken include is reported while the previous shader is kept
+    let common = sources["common.wgsl"].clone();
+    sources.insert(
+        "common.wgsl".to_string(),
+        format!("{common}\nfn broken() -> f32 {{ return undefined; }}"),
+    );
+    let error = rebuild_or_keep(&mut compute, || {
+        GPUPipeline::preprocess_shader(&sources, &formulas, "compute.wgsl", false)
+    })
+    .unwrap();
+    assert!(error.starts_with("common.wgsl:"), "{error}");
+    assert_eq!(compute, previous);
+
+    // Fixing it replaces the shader
+    sources.insert(
+        "common.wgsl".to_string(),
+        format!("{common}\nfn fixed() -> f32 {{ return 1.0; }}"),
+    );
+    let error = rebuild_or_keep(&mut compute, || {
+        GPUPipeline::preprocess_shader(&sources, &formulas, "compute.wgsl", false)
+    });
+    assert_eq!(error, None);
+    assert!(compute.contains("fn fixed()"));
+}
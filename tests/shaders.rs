@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use faraday_art::utils::{formula::FormulaSet, pipeline::GPUPipeline};
+
+/// Shader files which are compiled into modules, the others being included.
+const SHADER_MODULES: [&str; 3] = ["compute.wgsl", "post_processing.wgsl", "render.wgsl"];
+
+/// Returns the shader files embedded in the application.
+fn shader_sources() -> HashMap<String, String> {
+    GPUPipeline::SHADER_FILES
+        .iter()
+        .map(|(name, source)| (name.to_string(), source.to_string()))
+        .collect()
+}
+
+/// Asserts that every shader module preprocesses and validates.
+fn assert_valid_shaders(shader_f64: bool) {
+    let sources = shader_sources();
+    let formulas = FormulaSet::default();
+    for name in SHADER_MODULES {
+        if let Err(error) = GPUPipeline::preprocess_shader(&sources, &formulas, name, shader_f64) {
+            panic!("{error}");
+        }
+    }
+}
+
+// The double-single mode only changes the data passed to the f32 shaders
+
+#[test]
+fn f32_shaders_are_valid() {
+    assert_valid_shaders(false);
+}
+
+#[test]
+fn f64_shaders_are_valid() {
+    assert_valid_shaders(true);
+}
+
+#[test]
+fn preprocessed_shaders_have_no_directives() {
+    let sources = shader_sources();
+    let formulas = FormulaSet::default();
+    for name in SHADER_MODULES {
+        let source = GPUPipeline::preprocess_shader(&sources, &formulas, name, false).unwrap();
+        assert!(
+            source
+                .lines()
+                .all(|line| !line.trim_start().starts_with('#')),
+            "{name} still has directives"
+        );
+    }
+}
+
+#[test]
+fn shader_errors_point_at_their_original_line() {
+    // Break a line of an included file, whose line numbers are shifted in the
+    // preprocessed shader
+    let mut sources = shader_sources();
+    let common = sources["common.wgsl"].replace("let m = v - c;", "let m = v - undefined;");
+    let line = common
+        .lines()
+        .position(|line| line.contains("undefined"))
+        .unwrap()
+        + 1;
+    sources.insert("common.wgsl".to_string(), common);
+
+    let error =
+        GPUPipeline::preprocess_shader(&sources, &FormulaSet::default(), "compute.wgsl", false)
+            .unwrap_err();
+    let location = format!("common.wgsl:{line}: ");
+    assert!(error.starts_with(&location), "{error}");
+    assert!(error.ends_with("let m = v - undefined;"), "{error}");
+}